@@ -0,0 +1,85 @@
+use std::fmt;
+
+use crate::boredapi::{content_type, parse_activity, parse_body, Activity, Backend, ParseMode, CriteriaSelection, Error, DEFAULT_URL};
+
+/// Synchronous counterpart of [`BoredApi`](crate::boredapi::BoredApi), usable without an async
+/// runtime.
+#[derive(fmt::Debug, Clone)]
+pub struct BoredApiBlocking {
+    pub url: String,
+    pub client: reqwest::blocking::Client,
+}
+
+impl Default for BoredApiBlocking {
+    fn default() -> Self {
+        BoredApiBlocking::with_url(DEFAULT_URL)
+    }
+}
+
+impl BoredApiBlocking {
+    /// Creates an API wrapper sending requests to the given endpoint with the default client.
+    pub fn with_url(url: impl Into<String>) -> Self {
+        BoredApiBlocking { url: url.into(), client: reqwest::blocking::Client::new() }
+    }
+
+    pub fn random(&self) -> Result<Activity, Error> {
+        self.by_criteria(Ok)
+    }
+
+    pub fn by_criteria<F>(&self, selection: F) -> Result<Activity, Error>
+        where F: FnOnce(CriteriaSelection) -> Result<CriteriaSelection, Error> {
+        let sel = selection(CriteriaSelection::default())?;
+        sel.validate()?;
+
+        let mut r = self.client.get(&self.url).query(sel.parameters()).send()?;
+
+        if r.status().is_server_error() || r.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            r = r.error_for_status()?;
+        }
+
+        let headers = r.headers().clone();
+        parse_activity(parse_body(content_type(&headers), &r.text()?)?, Backend::Legacy, ParseMode::Lenient)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blocking::BoredApiBlocking;
+    use crate::boredapi::{ActivityType, Error, EXACT_ACCESSIBILITY, TYPE};
+    use crate::mock::{activity_json, MockResponse, MockServer};
+
+    #[test]
+    fn random() {
+        let server = MockServer::start(vec![MockResponse::json(activity_json(3943506))]);
+        let api = BoredApiBlocking::with_url(server.url());
+
+        for _ in 0..2 {
+            match api.random() {
+                Ok(a) => assert_eq!(a.key, 3943506),
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn by_criteria() {
+        let server = MockServer::start(vec![MockResponse::json(activity_json(3943506))]);
+
+        match BoredApiBlocking::with_url(server.url()).by_criteria(|sel| sel.set(TYPE, ActivityType::Education)) {
+            Ok(a) => assert_eq!(a.activity_type, ActivityType::Education),
+            Err(e) => panic!("{:?}", e),
+        }
+        assert!(server.requests()[0].starts_with("GET /api/activity?type=education "));
+    }
+
+    #[test]
+    fn no_activity() {
+        match BoredApiBlocking::default().by_criteria(|s| s.set(EXACT_ACCESSIBILITY, -1.0)) {
+            Ok(a) => panic!("{:?}", a),
+            Err(e) => match e {
+                Error::InvalidCriterion { name } => { assert_eq!(name, "accessibility") }
+                e => panic!("{:?}", e),
+            },
+        }
+    }
+}
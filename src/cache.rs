@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::{fmt, fs, sync};
+
+use crate::boredapi::{Activity, CriteriaSelection};
+
+/// Stores fetched activities in a JSON file, so that they survive restarts. The file maps query
+/// strings to activities and is read on first use.
+///
+/// The cache is best-effort: an unreadable file is treated as empty and failed writes are ignored.
+#[derive(fmt::Debug)]
+pub struct FileCache {
+    path: PathBuf,
+    entries: sync::Mutex<Option<HashMap<String, Activity>>>,
+}
+
+impl FileCache {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileCache { path: path.into(), entries: sync::Mutex::new(None) }
+    }
+
+    pub(crate) fn get(&self, sel: &CriteriaSelection) -> Option<Activity> {
+        let mut entries = self.entries.lock().unwrap();
+        self.load(&mut entries).get(&sel.to_string()).cloned()
+    }
+
+    pub(crate) fn insert(&self, sel: &CriteriaSelection, activity: &Activity) {
+        let mut entries = self.entries.lock().unwrap();
+        let entries = self.load(&mut entries);
+        entries.insert(sel.to_string(), activity.clone());
+
+        if let Ok(json) = serde_json::to_string(entries) {
+            let _ = fs::write(&self.path, json);
+        }
+    }
+
+    fn load<'a>(&self, entries: &'a mut Option<HashMap<String, Activity>>) -> &'a mut HashMap<String, Activity> {
+        entries.get_or_insert_with(|| {
+            fs::read(&self.path)
+                .ok()
+                .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+                .unwrap_or_default()
+        })
+    }
+}
@@ -2,18 +2,28 @@
 
 mod boredapi {
     use std::str::FromStr;
-    use strum_macros;
-    use std::{fmt, collections, marker};
-    use std::borrow::Borrow;
+    use std::{fmt, collections, error, hash, marker, sync, time};
     use std::cmp;
-    use url;
+    use std::convert::TryFrom;
     use std::marker::PhantomData;
+    #[cfg(feature = "reqwest")]
+    use futures::{future, stream, Stream};
+
+    #[cfg(feature = "serde")]
+    pub use crate::cache::FileCache;
+
+    /// Provides `ActivityType::iter()` enumerating all the activity types.
+    pub use strum::IntoEnumIterator;
 
     /// Represents a type of activity in Bored API.
-    #[derive(strum_macros::EnumString, strum_macros::ToString, cmp::PartialEq, cmp::Eq, fmt::Debug)]
+    ///
+    /// Defaults to [`ActivityType::Recreational`]. Usable as a `HashMap` key, but not `Copy` because
+    /// of [`ActivityType::Unknown`].
+    #[derive(strum_macros::EnumString, strum_macros::EnumIter, cmp::PartialEq, cmp::Eq, hash::Hash, fmt::Debug, Clone, Default)]
     pub enum ActivityType {
         #[strum(serialize = "education")]
         Education,
+        #[default]
         #[strum(serialize = "recreational")]
         Recreational,
         #[strum(serialize = "social")]
@@ -30,33 +40,334 @@ mod boredapi {
         Music,
         #[strum(serialize = "busywork")]
         Busywork,
+        /// A type unknown to this version of the wrapper, holding the type received from the API.
+        #[strum(disabled)]
+        Unknown(String),
+    }
+
+    impl ActivityType {
+        /// Parses a type case-insensitively, keeping unrecognized ones as [`ActivityType::Unknown`].
+        pub fn from_str_lenient(s: &str) -> Self {
+            ActivityType::from_str(&s.to_lowercase()).unwrap_or_else(|_| ActivityType::Unknown(s.to_string()))
+        }
+
+        /// Returns an emoji illustrating the type, a question mark for unknown types.
+        pub fn emoji(&self) -> &'static str {
+            match self {
+                ActivityType::Education => "📚",
+                ActivityType::Recreational => "🎨",
+                ActivityType::Social => "🎉",
+                ActivityType::Diy => "🔨",
+                ActivityType::Charity => "🤝",
+                ActivityType::Cooking => "🍳",
+                ActivityType::Relaxation => "🧘",
+                ActivityType::Music => "🎵",
+                ActivityType::Busywork => "🧹",
+                ActivityType::Unknown(_) => "❓",
+            }
+        }
+
+        /// Describes the type for users, in English only for now.
+        pub fn description(&self) -> &'static str {
+            match self {
+                ActivityType::Education => "Learn something new",
+                ActivityType::Recreational => "Have some fun",
+                ActivityType::Social => "Spend time with others",
+                ActivityType::Diy => "Make something yourself",
+                ActivityType::Charity => "Help someone out",
+                ActivityType::Cooking => "Cook something tasty",
+                ActivityType::Relaxation => "Take it easy",
+                ActivityType::Music => "Enjoy some music",
+                ActivityType::Busywork => "Get things done",
+                ActivityType::Unknown(_) => "Try something different",
+            }
+        }
+
+        /// Picks a known type with a probability proportional to its weight in `weights`. Types
+        /// missing from `weights` weigh 1, negative weights count as 0, and if all the weights are
+        /// 0 every type is equally likely.
+        pub fn weighted_random<R: rand::Rng + ?Sized>(weights: &collections::HashMap<ActivityType, f64>, rng: &mut R) -> Self {
+            let types: Vec<_> = ActivityType::iter().collect();
+            let weights: Vec<_> = types.iter().map(|t| weights.get(t).copied().unwrap_or(1.0).max(0.0)).collect();
+
+            let index = match rand::distributions::WeightedIndex::new(&weights) {
+                Ok(distribution) => rng.sample(distribution),
+                Err(_) => rng.gen_range(0..types.len()),
+            };
+            types[index].clone()
+        }
+    }
+
+    impl fmt::Display for ActivityType {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(match self {
+                ActivityType::Education => "education",
+                ActivityType::Recreational => "recreational",
+                ActivityType::Social => "social",
+                ActivityType::Diy => "diy",
+                ActivityType::Charity => "charity",
+                ActivityType::Cooking => "cooking",
+                ActivityType::Relaxation => "relaxation",
+                ActivityType::Music => "music",
+                ActivityType::Busywork => "busywork",
+                ActivityType::Unknown(s) => s,
+            })
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl serde::Serialize for ActivityType {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl<'de> serde::Deserialize<'de> for ActivityType {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let s = String::deserialize(deserializer)?;
+            Ok(ActivityType::from_str_lenient(&s))
+        }
     }
 
     /// Combines all possible errors of the API wrapper.
+    #[allow(clippy::enum_variant_names)]
     #[derive(fmt::Debug)]
     pub enum Error {
         /// Error returned by reqwest.
+        #[cfg(feature = "reqwest")]
         HttpError(reqwest::Error),
+        /// Error returned by API when no activity matches the criteria.
+        NoActivityFound,
         /// Error returned by API.
         ApiError(String),
         /// Error caused by a bad read of API response. Possible problems are invalid Bored API
         /// backend or bug in the wrapper.
         BadResponse,
+        /// Error caused by a criterion value rejected by its validator before sending the request.
+        InvalidCriterion { name: &'static str },
+        /// Error caused by a request not completing within the configured timeout.
+        Timeout,
+        /// Error caused by a missing or malformed field of API response.
+        Deserialization { field: String, reason: String },
+        /// Error caused by a range whose lower bound is greater than its upper bound.
+        InvalidRange { min: &'static str, max: &'static str },
+        /// Error caused by no fetched activity satisfying a local predicate.
+        ExhaustedAttempts { attempts: usize },
+        /// Error caused by an endpoint or an activity link that isn't a valid URL.
+        InvalidUrl(url::ParseError),
+        /// Error caused by a text that isn't valid JSON.
+        Json(serde_json::Error),
+        /// Error caused by a response body that is empty or isn't JSON, e.g. an HTML error page. The
+        /// beginning of the body is kept to help debugging.
+        UnexpectedBody { content_type: Option<String>, snippet: String },
+        /// Error caused by two criteria that can't be set together, such as an exact price and a
+        /// price range.
+        ConflictingCriteria { a: &'static str, b: &'static str },
+        /// Error caused by an environment variable with an invalid value.
+        InvalidEnvVar { name: &'static str, value: String },
+        /// Error caused by a header value that can't be sent.
+        InvalidHeader { name: &'static str, value: String },
+        /// Error returned by an HTTP client other than reqwest, with the status of the response if
+        /// one was received.
+        Transport { status: Option<u16>, message: String },
+        /// Error caused by reading or writing a file.
+        Io(std::io::Error),
+        /// Error caused by an operation cancelled with a [`CancellationToken`].
+        Cancelled,
+        /// Error caused by a response that couldn't be parsed into an [`Activity`], keeping the
+        /// received JSON next to the parsing error. Returned by
+        /// [`BoredApi::by_criteria_raw`] and [`BoredApi::random_raw`].
+        UnparsedResponse { raw: serde_json::Value, error: Box<Error> },
+    }
+
+    impl Error {
+        /// Whether the request that caused the error may succeed if sent again, i.e. whether the
+        /// error is a timeout, a connection error or a 5xx response.
+        pub fn is_retryable(&self) -> bool {
+            match self {
+                Error::Timeout => true,
+                #[cfg(feature = "reqwest")]
+                Error::HttpError(e) => e.is_timeout()
+                    || e.is_connect()
+                    || e.status().is_some_and(|s| s.is_server_error()),
+                Error::Transport { status, .. } => status.is_none_or(|s| (500..600).contains(&s)),
+                _ => false,
+            }
+        }
+
+        /// Returns the status of the response that caused the error, if any.
+        #[cfg(feature = "reqwest")]
+        pub fn status_code(&self) -> Option<reqwest::StatusCode> {
+            match self {
+                Error::HttpError(e) => e.status(),
+                Error::Transport { status, .. } => status.and_then(|s| reqwest::StatusCode::from_u16(s).ok()),
+                _ => None,
+            }
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    impl From<reqwest::Error> for Error {
+        fn from(e: reqwest::Error) -> Self {
+            if e.is_timeout() { Error::Timeout } else { Error::HttpError(e) }
+        }
+    }
+
+    impl From<url::ParseError> for Error {
+        fn from(e: url::ParseError) -> Self {
+            Error::InvalidUrl(e)
+        }
+    }
+
+    impl From<std::io::Error> for Error {
+        fn from(e: std::io::Error) -> Self {
+            Error::Io(e)
+        }
+    }
+
+    impl From<serde_json::Error> for Error {
+        fn from(e: serde_json::Error) -> Self {
+            Error::Json(e)
+        }
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                #[cfg(feature = "reqwest")]
+                Error::HttpError(e) => write!(f, "HTTP error: {}", e),
+                Error::NoActivityFound => write!(f, "no activity found with the specified parameters"),
+                Error::ApiError(msg) => write!(f, "API error: {}", msg),
+                Error::BadResponse => write!(f, "malformed or unexpected API response"),
+                Error::InvalidCriterion { name } => write!(f, "invalid value for criterion `{}`", name),
+                Error::Timeout => write!(f, "request timed out"),
+                Error::Deserialization { field, reason } =>
+                    write!(f, "invalid field `{}` in API response: {}", field, reason),
+                Error::InvalidRange { min, max } => write!(f, "`{}` is greater than `{}`", min, max),
+                Error::ExhaustedAttempts { attempts } =>
+                    write!(f, "no activity satisfied the predicate in {} attempts", attempts),
+                Error::InvalidUrl(e) => write!(f, "invalid URL: {}", e),
+                Error::Json(e) => write!(f, "invalid JSON: {}", e),
+                Error::UnexpectedBody { content_type: Some(content_type), snippet } =>
+                    write!(f, "unexpected `{}` response: {:?}", content_type, snippet),
+                Error::UnexpectedBody { content_type: None, snippet } => write!(f, "unexpected response: {:?}", snippet),
+                Error::ConflictingCriteria { a, b } => write!(f, "criteria `{}` and `{}` can't be set together", a, b),
+                Error::InvalidEnvVar { name, value } =>
+                    write!(f, "invalid value of environment variable `{}`: {:?}", name, value),
+                Error::InvalidHeader { name, value } => write!(f, "invalid value of header `{}`: {:?}", name, value),
+                Error::Transport { message, .. } => write!(f, "HTTP error: {}", message),
+                Error::Io(e) => write!(f, "I/O error: {}", e),
+                Error::Cancelled => write!(f, "operation cancelled"),
+                Error::UnparsedResponse { error, .. } => write!(f, "unparsed response: {}", error),
+            }
+        }
+    }
+
+    impl error::Error for Error {
+        fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+            match self {
+                #[cfg(feature = "reqwest")]
+                Error::HttpError(e) => Some(e),
+                Error::InvalidUrl(e) => Some(e),
+                Error::Json(e) => Some(e),
+                Error::Io(e) => Some(e),
+                Error::UnparsedResponse { error, .. } => Some(error.as_ref()),
+                _ => None,
+            }
+        }
+    }
+
+    /// Classifies the price of an activity: 0 is free, below 0.33 is low, below 0.66 is medium,
+    /// anything else is high.
+    #[derive(fmt::Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum PriceCategory {
+        Free,
+        Low,
+        Medium,
+        High,
+    }
+
+    impl fmt::Display for PriceCategory {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(match self {
+                PriceCategory::Free => "free",
+                PriceCategory::Low => "low",
+                PriceCategory::Medium => "medium",
+                PriceCategory::High => "high",
+            })
+        }
+    }
+
+    /// Classifies the number of participants of an activity: 1 is solo, 2 is a pair, 3 to 5 is a
+    /// small group, anything above is a large group. The API never reports 0 participants, such
+    /// activities are unknown.
+    #[derive(fmt::Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ParticipantsCategory {
+        Unknown,
+        Solo,
+        Pair,
+        SmallGroup,
+        LargeGroup,
+    }
+
+    impl fmt::Display for ParticipantsCategory {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(match self {
+                ParticipantsCategory::Unknown => "unknown",
+                ParticipantsCategory::Solo => "solo",
+                ParticipantsCategory::Pair => "pair",
+                ParticipantsCategory::SmallGroup => "small group",
+                ParticipantsCategory::LargeGroup => "large group",
+            })
+        }
     }
 
     /// Represents Activity entity of Bored API.
-    #[derive(fmt::Debug)]
+    ///
+    /// Activities are identified by their keys: equality and hashing only consider the `key` field.
+    #[derive(fmt::Debug, Clone)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize))]
     pub struct Activity {
+        #[cfg_attr(feature = "serde", serde(rename = "activity"))]
         pub description: String,
         pub accessibility: f64,
+        #[cfg_attr(feature = "serde", serde(rename = "type"))]
         pub activity_type: ActivityType,
         pub participants: u64,
         pub price: f64,
+        #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_link"))]
         pub link: Option<url::Url>,
+        #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_key"))]
         pub key: u64,
+        /// Fields of the response not mapped to the other fields, kept as received.
+        #[cfg_attr(feature = "serde", serde(flatten))]
+        pub extra: collections::HashMap<String, serde_json::Value>,
+        #[cfg_attr(feature = "serde", serde(skip))]
         dummy: PhantomData<()>,
     }
 
+    /// Accepts the Bored API response shape, the same way the clients do.
+    #[cfg(feature = "serde")]
+    impl<'de> serde::Deserialize<'de> for Activity {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let json = serde_json::Value::deserialize(deserializer)?;
+            Activity::try_from(json).map_err(serde::de::Error::custom)
+        }
+    }
+
+    /// The API sends an empty string when an activity has no link.
+    #[cfg(feature = "serde")]
+    fn serialize_link<S: serde::Serializer>(link: &Option<url::Url>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(link.as_ref().map(url::Url::as_str).unwrap_or(""))
+    }
+
+    /// The API sends the key as a numeric string.
+    #[cfg(feature = "serde")]
+    fn serialize_key<S: serde::Serializer>(key: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(key)
+    }
+
     impl Activity {
         pub fn new(description: String,
                    accessibility: f64,
@@ -65,7 +376,181 @@ mod boredapi {
                    price: f64,
                    link: Option<url::Url>,
                    key: u64) -> Self {
-            Activity { description, accessibility, activity_type, participants, price, link, key, dummy: PhantomData {} }
+            Activity {
+                description,
+                accessibility,
+                activity_type,
+                participants,
+                price,
+                link,
+                key,
+                extra: collections::HashMap::new(),
+                dummy: PhantomData {},
+            }
+        }
+
+        /// Like [`Activity::new`], but rejects the values the API would never produce, using the
+        /// checks of the corresponding criteria.
+        pub fn try_new(description: String,
+                       accessibility: f64,
+                       activity_type: ActivityType,
+                       participants: u64,
+                       price: f64,
+                       link: Option<url::Url>,
+                       key: u64) -> Result<Self, Error> {
+            EXACT_ACCESSIBILITY.check(accessibility)?;
+            PARTICIPANTS.check(participants)?;
+            EXACT_PRICE.check(price)?;
+            KEY.check(key)?;
+            Ok(Activity::new(description, accessibility, activity_type, participants, price, link, key))
+        }
+
+        /// Whether the activity costs nothing, i.e. its price is 0.
+        pub fn is_free(&self) -> bool {
+            self.price == 0.0
+        }
+
+        /// Whether the activity is cheap, i.e. its price is below 0.25.
+        pub fn is_cheap(&self) -> bool {
+            self.price < 0.25
+        }
+
+        pub fn price_category(&self) -> PriceCategory {
+            match self.price {
+                _ if self.is_free() => PriceCategory::Free,
+                p if p < 0.33 => PriceCategory::Low,
+                p if p < 0.66 => PriceCategory::Medium,
+                _ => PriceCategory::High,
+            }
+        }
+
+        /// Returns the accessibility as a percentage, clamped to `0..=100`.
+        pub fn accessibility_percentage(&self) -> f64 {
+            (self.accessibility * 100.0).clamp(0.0, 100.0)
+        }
+
+        /// Describes the accessibility in words. Lower accessibility factors mean the activity is
+        /// easier to do.
+        pub fn accessibility_label(&self) -> &'static str {
+            match self.accessibility_percentage() {
+                p if p < 25.0 => "very accessible",
+                p if p < 50.0 => "accessible",
+                p if p < 75.0 => "moderately accessible",
+                _ => "hardly accessible",
+            }
+        }
+
+        pub fn participants_category(&self) -> ParticipantsCategory {
+            match self.participants {
+                0 => ParticipantsCategory::Unknown,
+                1 => ParticipantsCategory::Solo,
+                2 => ParticipantsCategory::Pair,
+                3..=5 => ParticipantsCategory::SmallGroup,
+                _ => ParticipantsCategory::LargeGroup,
+            }
+        }
+
+        /// Compares activities by price, usable with `sort_by`.
+        pub fn cmp_by_price(&self, other: &Self) -> cmp::Ordering {
+            self.price.total_cmp(&other.price)
+        }
+
+        /// Compares activities by accessibility, usable with `sort_by`.
+        pub fn cmp_by_accessibility(&self, other: &Self) -> cmp::Ordering {
+            self.accessibility.total_cmp(&other.accessibility)
+        }
+
+        /// Returns a builder of activities, convenient for fixtures.
+        pub fn builder() -> ActivityBuilder {
+            ActivityBuilder::default()
+        }
+    }
+
+    /// Builds an [`Activity`] field by field. Unset fields default to an empty description,
+    /// accessibility 0, [`ActivityType::Recreational`], 1 participant, price 0, no link and key
+    /// 1000000.
+    #[derive(fmt::Debug, Clone)]
+    pub struct ActivityBuilder {
+        activity: Activity,
+    }
+
+    impl Default for ActivityBuilder {
+        fn default() -> Self {
+            ActivityBuilder {
+                activity: Activity::new(String::new(), 0.0, ActivityType::default(), 1, 0.0, None, 1_000_000),
+            }
+        }
+    }
+
+    impl ActivityBuilder {
+        pub fn description(mut self, description: impl Into<String>) -> Self {
+            self.activity.description = description.into();
+            self
+        }
+
+        pub fn accessibility(mut self, accessibility: f64) -> Self {
+            self.activity.accessibility = accessibility;
+            self
+        }
+
+        pub fn activity_type(mut self, activity_type: ActivityType) -> Self {
+            self.activity.activity_type = activity_type;
+            self
+        }
+
+        pub fn participants(mut self, participants: u64) -> Self {
+            self.activity.participants = participants;
+            self
+        }
+
+        pub fn price(mut self, price: f64) -> Self {
+            self.activity.price = price;
+            self
+        }
+
+        pub fn link(mut self, link: url::Url) -> Self {
+            self.activity.link = Some(link);
+            self
+        }
+
+        pub fn key(mut self, key: u64) -> Self {
+            self.activity.key = key;
+            self
+        }
+
+        pub fn build(self) -> Activity {
+            self.activity
+        }
+    }
+
+    impl PartialEq for Activity {
+        fn eq(&self, other: &Self) -> bool {
+            self.key == other.key
+        }
+    }
+
+    impl Eq for Activity {}
+
+    impl hash::Hash for Activity {
+        fn hash<H: hash::Hasher>(&self, state: &mut H) {
+            self.key.hash(state);
+        }
+    }
+
+    impl fmt::Display for Activity {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{} (type: {}, participants: {}, price: {}, accessibility: {}",
+                   self.description,
+                   self.activity_type,
+                   self.participants,
+                   self.price,
+                   self.accessibility)?;
+
+            if let Some(link) = &self.link {
+                write!(f, ", link: {}", link)?;
+            }
+
+            write!(f, ")")
         }
     }
 
@@ -75,44 +560,54 @@ mod boredapi {
         validate: fn(T) -> bool,
     }
 
+    impl<T> ActivityCriterion<T> {
+        fn check(&self, value: T) -> Result<(), Error> {
+            if (self.validate)(value) {
+                Ok(())
+            } else {
+                Err(Error::InvalidCriterion { name: self.name })
+            }
+        }
+    }
+
     pub const EXACT_ACCESSIBILITY: ActivityCriterion<f64> = ActivityCriterion {
         name: "accessibility",
-        validate: |v| (0.0..1.0).contains(&v),
+        validate: |v| (0.0..=1.0).contains(&v),
     };
 
     pub const EXACT_PRICE: ActivityCriterion<f64> = ActivityCriterion {
         name: "price",
-        validate: |v| (0.0..1.0).contains(&v),
+        validate: |v| (0.0..=1.0).contains(&v),
     };
 
     pub const KEY: ActivityCriterion<u64> = ActivityCriterion {
         name: "key",
-        validate: |v| (1000000..9999999).contains(&v),
+        validate: |v| (1_000_000..=9_999_999).contains(&v),
     };
 
     pub const MAX_ACCESSIBILITY: ActivityCriterion<f64> = ActivityCriterion {
         name: "maxaccessibility",
-        validate: |v| (0.0..1.0).contains(&v),
+        validate: |v| (0.0..=1.0).contains(&v),
     };
 
     pub const MAX_PRICE: ActivityCriterion<f64> = ActivityCriterion {
         name: "maxprice",
-        validate: |v| (0.0..1.0).contains(&v),
+        validate: |v| (0.0..=1.0).contains(&v),
     };
 
     pub const MIN_ACCESSIBILITY: ActivityCriterion<f64> = ActivityCriterion {
         name: "minaccessibility",
-        validate: |v| (0.0..1.0).contains(&v),
+        validate: |v| (0.0..=1.0).contains(&v),
     };
 
     pub const MIN_PRICE: ActivityCriterion<f64> = ActivityCriterion {
         name: "minprice",
-        validate: |v| (0.0..1.0).contains(&v),
+        validate: |v| (0.0..=1.0).contains(&v),
     };
 
     pub const PARTICIPANTS: ActivityCriterion<u64> = ActivityCriterion {
         name: "participants",
-        validate: |v| (0..u64::MAX).contains(&v),
+        validate: |v| v >= 1,
     };
 
     pub const TYPE: ActivityCriterion<ActivityType> = ActivityCriterion {
@@ -120,136 +615,3149 @@ mod boredapi {
         validate: |_| true,
     };
 
-    #[derive(fmt::Debug)]
-    pub struct CriteriaSelection { parameters: collections::HashMap<String, String> }
+    /// Key of an activity, a 7-digit number. Accepted by [`KEY`], and converts into `u64`.
+    #[derive(fmt::Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub struct Key(u64);
 
-    impl CriteriaSelection {
-        pub fn set<T: ToString>(mut self, criterion: ActivityCriterion<T>, value: T) -> Self {
-            self.parameters.insert(criterion.name.to_string(), value.to_string());
-            return self;
+    impl Key {
+        /// Fails with [`Error::InvalidCriterion`] if `key` isn't in `1_000_000..=9_999_999`.
+        pub fn new(key: u64) -> Result<Self, Error> {
+            KEY.check(key)?;
+            Ok(Key(key))
         }
     }
 
-    impl Clone for CriteriaSelection {
-        fn clone(&self) -> Self {
-            CriteriaSelection { parameters: self.parameters.clone() }
+    impl fmt::Display for Key {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.0)
         }
     }
 
-    impl Default for CriteriaSelection {
-        fn default() -> Self {
-            CriteriaSelection { parameters: collections::HashMap::new() }
+    impl From<Key> for u64 {
+        fn from(key: Key) -> Self {
+            key.0
         }
     }
 
-    #[derive(fmt::Debug)]
-    pub struct BoredApi {
-        pub url: &'static str,
-        pub client: reqwest::Client,
+    /// Accessibility factor of an activity, from 0 (most accessible) to 1. Accepted by the
+    /// accessibility criteria, and converts into `f64`.
+    #[derive(fmt::Debug, Clone, Copy, PartialEq, PartialOrd)]
+    pub struct Accessibility(f64);
+
+    impl Accessibility {
+        /// Fails with [`Error::InvalidCriterion`] if `accessibility` isn't in `0.0..=1.0`.
+        pub fn new(accessibility: f64) -> Result<Self, Error> {
+            EXACT_ACCESSIBILITY.check(accessibility)?;
+            // Adding zero turns -0.0 into 0.0, which would otherwise display as "-0%".
+            Ok(Accessibility(accessibility + 0.0))
+        }
+
+        pub fn value(&self) -> f64 {
+            self.0
+        }
     }
 
-    impl Default for BoredApi {
-        fn default() -> Self {
-            BoredApi { url: "http://www.boredapi.com/api/activity", client: reqwest::Client::new() }
+    /// Displays the factor as a percentage.
+    impl fmt::Display for Accessibility {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}%", self.0 * 100.0)
         }
     }
 
-    impl Clone for BoredApi {
-        fn clone(&self) -> Self {
-            return BoredApi { url: self.url, client: self.client.clone() };
+    impl From<Accessibility> for f64 {
+        fn from(accessibility: Accessibility) -> Self {
+            accessibility.0
         }
     }
 
-    impl BoredApi {
-        pub async fn random(self) -> Result<Activity, Error> {
-            self.by_criteria(|s| s).await
+    /// Price of an activity, from 0 (free) to 1. Accepted by the price criteria, and converts into
+    /// `f64`.
+    #[derive(fmt::Debug, Clone, Copy, PartialEq, PartialOrd)]
+    pub struct Price(f64);
+
+    impl Price {
+        /// Fails with [`Error::InvalidCriterion`] if `price` isn't in `0.0..=1.0`.
+        pub fn new(price: f64) -> Result<Self, Error> {
+            EXACT_PRICE.check(price)?;
+            Ok(Price(price + 0.0))
         }
 
-        pub async fn by_criteria<F: FnOnce(CriteriaSelection) -> CriteriaSelection>(self, selection: F) -> Result<Activity, Error> {
-            let mut sel = CriteriaSelection::default();
-            sel = selection(sel);
+        /// The price of free activities.
+        pub const fn free() -> Self {
+            Price(0.0)
+        }
 
-            match self.client.get(self.url).query(&sel.parameters.borrow()).send().await {
-                Ok(r) => match r.json::<serde_json::Value>().await {
-                    Ok(val) => self.deserialize(val),
-                    Err(r) => Err(Error::HttpError(r))
-                },
-                Err(r) => Err(Error::HttpError(r)),
+        pub fn value(&self) -> f64 {
+            self.0
+        }
+    }
+
+    impl fmt::Display for Price {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl From<Price> for f64 {
+        fn from(price: Price) -> Self {
+            price.0
+        }
+    }
+
+    /// A value accepted by an [`ActivityCriterion<T>`]: `T` itself, or one of the checked
+    /// [`Price`], [`Accessibility`] and [`Key`].
+    pub trait CriterionValue<T> {
+        fn into_criterion_value(self) -> T;
+    }
+
+    impl<T> CriterionValue<T> for T {
+        fn into_criterion_value(self) -> T {
+            self
+        }
+    }
+
+    impl CriterionValue<f64> for Price {
+        fn into_criterion_value(self) -> f64 {
+            self.into()
+        }
+    }
+
+    impl CriterionValue<f64> for Accessibility {
+        fn into_criterion_value(self) -> f64 {
+            self.into()
+        }
+    }
+
+    impl CriterionValue<u64> for Key {
+        fn into_criterion_value(self) -> u64 {
+            self.into()
+        }
+    }
+
+    #[derive(fmt::Debug, Default)]
+    pub struct CriteriaSelection { parameters: collections::HashMap<String, String> }
+
+    impl CriteriaSelection {
+        /// Sets the value of the given criterion, running its validator first.
+        pub fn set<T: ToString>(mut self, criterion: ActivityCriterion<T>, value: impl CriterionValue<T>) -> Result<Self, Error> {
+            let value = value.into_criterion_value();
+            let string = value.to_string();
+            criterion.check(value)?;
+            self.parameters.insert(criterion.name.to_string(), string);
+            Ok(self)
+        }
+
+        /// Sets an arbitrary query parameter, bypassing all the [`ActivityCriterion`] checks.
+        pub fn set_raw(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+            self.parameters.insert(key.into(), value.into());
+            self
+        }
+
+        /// Sets both bounds of the price, checking that `min` is not greater than `max`.
+        pub fn set_price_range(self, min: impl CriterionValue<f64>, max: impl CriterionValue<f64>) -> Result<Self, Error> {
+            self.set_range(MIN_PRICE, MAX_PRICE, min.into_criterion_value(), max.into_criterion_value())
+        }
+
+        /// Sets both bounds of the accessibility, checking that `min` is not greater than `max`.
+        pub fn set_accessibility_range(self,
+                                       min: impl CriterionValue<f64>,
+                                       max: impl CriterionValue<f64>) -> Result<Self, Error> {
+            self.set_range(MIN_ACCESSIBILITY, MAX_ACCESSIBILITY, min.into_criterion_value(), max.into_criterion_value())
+        }
+
+        fn set_range(self,
+                     min_criterion: ActivityCriterion<f64>,
+                     max_criterion: ActivityCriterion<f64>,
+                     min: f64,
+                     max: f64) -> Result<Self, Error> {
+            if min > max {
+                return Err(Error::InvalidRange { min: min_criterion.name, max: max_criterion.name });
             }
+
+            self.set(min_criterion, min)?.set(max_criterion, max)
         }
 
-        #[inline]
-        fn deserialize(self, json: serde_json::Value) -> Result<Activity, Error> {
-            macro_rules! extract_field {
-            ($name:expr, $extractor:ident) => {
-                json.get($name).ok_or(Error::BadResponse)?.$extractor().ok_or(Error::BadResponse)?
-            };
+        /// Unsets the given criterion.
+        pub fn remove<T>(mut self, criterion: ActivityCriterion<T>) -> Self {
+            self.parameters.remove(criterion.name);
+            self
+        }
+
+        /// Unsets all the criteria.
+        pub fn clear(mut self) -> Self {
+            self.parameters.clear();
+            self
+        }
+
+        /// Combines two selections. The criteria set in both take the value from `other`.
+        pub fn merge(mut self, other: CriteriaSelection) -> Self {
+            self.parameters.extend(other.parameters);
+            self
+        }
+
+        /// Checks that no exact value is set together with a bound of the same field, which the API
+        /// doesn't support. Done before sending every request.
+        pub fn validate(&self) -> Result<(), Error> {
+            let conflicts = [(EXACT_PRICE.name, MIN_PRICE.name),
+                             (EXACT_PRICE.name, MAX_PRICE.name),
+                             (EXACT_ACCESSIBILITY.name, MIN_ACCESSIBILITY.name),
+                             (EXACT_ACCESSIBILITY.name, MAX_ACCESSIBILITY.name)];
+
+            match conflicts.iter().find(|(a, b)| self.parameters.contains_key(*a) && self.parameters.contains_key(*b)) {
+                Some(&(a, b)) => Err(Error::ConflictingCriteria { a, b }),
+                None => Ok(()),
             }
+        }
 
-            if let Some(err) = json.get("error") {
-                return Err(err
-                    .as_str()
-                    .map(|s| Error::ApiError(s.to_string()))
-                    .unwrap_or(Error::BadResponse));
+        fn set_optional<T: ToString>(self, criterion: ActivityCriterion<T>, value: Option<T>) -> Result<Self, Error> {
+            match value {
+                Some(value) => self.set(criterion, value),
+                None => Ok(self),
             }
+        }
 
-            Ok(Activity::new(
-                extract_field!("activity", as_str).to_string(),
-                extract_field!("accessibility", as_f64),
-                ActivityType::from_str(extract_field!("type", as_str))
-                    .map_err(|_| Error::BadResponse)?,
-                extract_field!("participants", as_u64),
-                extract_field!("price", as_f64),
-                match extract_field!("link", as_str) {
-                    "" => None,
-                    s => Some(url::Url::parse(s).map_err(|_| Error::BadResponse)?),
-                },
-                extract_field!("key", as_str).parse::<u64>().map_err(|e| Error::BadResponse)?,
-            ))
+        /// Returns the query parameters that will be sent.
+        pub fn parameters(&self) -> &collections::HashMap<String, String> {
+            &self.parameters
+        }
+
+        /// Returns the value set for the given criterion.
+        pub fn get<T>(&self, criterion: &ActivityCriterion<T>) -> Option<&str> {
+            self.parameters.get(criterion.name).map(String::as_str)
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::boredapi;
-    use tokio::runtime::Runtime;
-    use crate::boredapi::{Error, Activity};
+    /// Renders the query string that will be sent, with the parameters sorted by name.
+    impl fmt::Display for CriteriaSelection {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let mut parameters: Vec<_> = self.parameters.iter().collect();
+            parameters.sort();
+            f.write_str(&url::form_urlencoded::Serializer::new(String::new()).extend_pairs(parameters).finish())
+        }
+    }
 
-    macro_rules! aw {
-    ($e:expr) => {
-        Runtime::new().expect("").block_on($e)
-    };
-  }
+    impl Clone for CriteriaSelection {
+        fn clone(&self) -> Self {
+            CriteriaSelection { parameters: self.parameters.clone() }
+        }
+    }
 
-    #[test]
-    fn random() {
-        match aw!(boredapi::BoredApi::default().random()) {
-            Ok(a) => { println!("{:?}", a); }
-            Err(_) => assert!(false),
+    /// Criteria of a query as plain data, easier to store than a closure building a
+    /// [`CriteriaSelection`]. Only the `Some` fields are sent.
+    #[derive(fmt::Debug, Clone, Default, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+    pub struct ActivityQuery {
+        pub activity_type: Option<ActivityType>,
+        pub participants: Option<u64>,
+        pub price: Option<f64>,
+        pub min_price: Option<f64>,
+        pub max_price: Option<f64>,
+        pub accessibility: Option<f64>,
+        pub min_accessibility: Option<f64>,
+        pub max_accessibility: Option<f64>,
+        pub key: Option<u64>,
+    }
+
+    /// Sets the `Some` fields of the query, failing if any of them is rejected by its criterion.
+    impl TryFrom<ActivityQuery> for CriteriaSelection {
+        type Error = Error;
+
+        fn try_from(query: ActivityQuery) -> Result<Self, Self::Error> {
+            CriteriaSelection::default()
+                .set_optional(TYPE, query.activity_type)?
+                .set_optional(PARTICIPANTS, query.participants)?
+                .set_optional(EXACT_PRICE, query.price)?
+                .set_optional(MIN_PRICE, query.min_price)?
+                .set_optional(MAX_PRICE, query.max_price)?
+                .set_optional(EXACT_ACCESSIBILITY, query.accessibility)?
+                .set_optional(MIN_ACCESSIBILITY, query.min_accessibility)?
+                .set_optional(MAX_ACCESSIBILITY, query.max_accessibility)?
+                .set_optional(KEY, query.key)
         }
     }
 
-    #[test]
-    fn by_criteria() {
-        match aw!(boredapi::BoredApi::default().by_criteria(|sel| sel.set(boredapi::TYPE, boredapi::ActivityType::Busywork))) {
-            Ok(a) => {
-                assert_eq!(a.activity_type, boredapi::ActivityType::Busywork);
-                println!("{:?}", a)
-            }
-            Err(_) => assert!(false),
+    /// Identifies the response shape of a Bored API deployment.
+    #[derive(fmt::Debug, Clone, Copy, PartialEq, Eq)]
+    pub(crate) enum Backend {
+        /// The original API at boredapi.com.
+        Legacy,
+        /// The community mirror at bored-api.appbrewery.com.
+        AppBrewery,
+    }
+
+    /// Describes how responses deviating from the documented shape are handled.
+    #[derive(fmt::Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum ParseMode {
+        /// Unrecognized activity types are kept as [`ActivityType::Unknown`], a missing `link` is
+        /// treated as no link, numbers sent as numeric strings are accepted and unexpected fields
+        /// are ignored.
+        #[default]
+        Lenient,
+        /// Unrecognized activity types, a missing `link`, numbers sent as strings and unexpected
+        /// fields are reported as [`Error::Deserialization`].
+        Strict,
+    }
+
+    trait LenientNumber {
+        /// Reads a number sent either as a JSON number or as a numeric string.
+        fn as_lenient_f64(&self) -> Option<f64>;
+    }
+
+    impl LenientNumber for serde_json::Value {
+        fn as_lenient_f64(&self) -> Option<f64> {
+            self.as_f64().or_else(|| self.as_str()?.parse().ok())
         }
     }
 
-    #[test]
-    fn no_activity() {
-        match aw!(boredapi::BoredApi::default().by_criteria(|s| s.set(boredapi::EXACT_ACCESSIBILITY, -1.0))) {
-            Ok(_) => assert!(false),
-            Err(e) => match e {
-                Error::HttpError(_) => assert!(false),
-                Error::ApiError(e) => { assert_eq!(e, "No activity found with the specified parameters") }
-                Error::BadResponse => assert!(false),
-            },
+    /// Parses a value in the Bored API response shape, e.g. read from a file.
+    impl TryFrom<serde_json::Value> for Activity {
+        type Error = Error;
+
+        fn try_from(json: serde_json::Value) -> Result<Self, Self::Error> {
+            parse_activity(json, Backend::Legacy, ParseMode::Lenient)
+        }
+    }
+
+    /// Parses a text in the Bored API response shape.
+    impl FromStr for Activity {
+        type Err = Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Activity::try_from(serde_json::from_str::<serde_json::Value>(s)?)
+        }
+    }
+
+    /// Message sent by API when no activity matches the criteria.
+    const NO_ACTIVITY_FOUND: &str = "No activity found with the specified parameters";
+
+    /// Parses a response of Bored API, shared by all the clients.
+    pub(crate) fn parse_activity(json: serde_json::Value, backend: Backend, mode: ParseMode) -> Result<Activity, Error> {
+        if let Some(err) = json.get("error") {
+            return Err(match err.as_str() {
+                Some(NO_ACTIVITY_FOUND) => Error::NoActivityFound,
+                Some(s) => Error::ApiError(s.to_string()),
+                None => Error::BadResponse,
+            });
+        }
+
+        if !json.is_object() {
+            return Err(Error::BadResponse);
+        }
+
+        let invalid = |field: &str, reason: &str| Error::Deserialization {
+            field: field.to_string(),
+            reason: reason.to_string(),
+        };
+
+        // Names of the serialized fields of `Activity`, never kept in `extra` so that they aren't
+        // serialized twice.
+        const SERIALIZED: &[&str] = &["activity", "accessibility", "type", "participants", "price", "link", "key"];
+
+        let mapped: &[&str] = match backend {
+            Backend::Legacy => SERIALIZED,
+            Backend::AppBrewery => &["activity", "availability", "type", "participants", "price", "link", "key"],
+        };
+        // Sent by the mirror, but not mapped to fields of `Activity`. Its textual `accessibility` is
+        // dropped.
+        let documented: &[&str] = match backend {
+            Backend::Legacy => &[],
+            Backend::AppBrewery => &["accessibility", "duration", "kidFriendly"],
+        };
+        let extra: collections::HashMap<_, _> = json.as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(k, _)| !mapped.contains(&k.as_str()) && !SERIALIZED.contains(&k.as_str()))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+
+        if mode == ParseMode::Strict {
+            if let Some(field) = extra.keys().find(|k| !documented.contains(&k.as_str())) {
+                return Err(invalid(field, "unexpected field"));
+            }
+        }
+
+        macro_rules! extract_field {
+            ($name:expr, $extractor:ident, $expected:expr) => {
+                json.get($name)
+                    .ok_or_else(|| invalid($name, "missing field"))?
+                    .$extractor()
+                    .ok_or_else(|| invalid($name, $expected))?
+            };
+        }
+
+        macro_rules! extract_number {
+            ($name:expr) => {
+                match mode {
+                    ParseMode::Lenient => extract_field!($name, as_lenient_f64, "expected number"),
+                    ParseMode::Strict => extract_field!($name, as_f64, "expected number"),
+                }
+            };
+        }
+
+        let mut activity = Activity::new(
+            extract_field!("activity", as_str, "expected string").to_string(),
+            match backend {
+                Backend::Legacy => extract_number!("accessibility"),
+                // The mirror sends a textual description as `accessibility`.
+                Backend::AppBrewery => extract_number!("availability"),
+            },
+            match mode {
+                ParseMode::Lenient => ActivityType::from_str_lenient(extract_field!("type", as_str, "expected string")),
+                ParseMode::Strict => ActivityType::from_str(extract_field!("type", as_str, "expected string"))
+                    .map_err(|_| invalid("type", "unknown activity type"))?,
+            },
+            extract_field!("participants", as_u64, "expected unsigned integer"),
+            extract_number!("price"),
+            match (json.get("link"), mode) {
+                (None, ParseMode::Lenient) => None,
+                _ => match extract_field!("link", as_str, "expected string") {
+                    "" => None,
+                    s => Some(url::Url::parse(s)?),
+                },
+            },
+            extract_field!("key", as_str, "expected numeric string")
+                .parse::<u64>()
+                .map_err(|_| invalid("key", "expected numeric string"))?,
+        );
+        activity.extra = extra;
+        Ok(activity)
+    }
+
+    /// Describes how failed requests are retried.
+    ///
+    /// Only timeouts, connection errors and 5xx responses are retried. The delay before the retry
+    /// number `n` (starting from 0) is `base_delay * 2^n` capped at `max_delay`, randomly reduced by
+    /// up to a half if `jitter` is set.
+    ///
+    /// The default policy retries 3 times, starting from 100ms up to 5s, with jitter.
+    #[derive(fmt::Debug, Clone, Copy)]
+    pub struct RetryPolicy {
+        pub max_retries: u32,
+        pub base_delay: time::Duration,
+        pub max_delay: time::Duration,
+        pub jitter: bool,
+        /// Called with the number of the upcoming retry (starting from 1) and the error of the
+        /// failed attempt.
+        pub on_retry: Option<fn(u32, &Error)>,
+    }
+
+    impl Default for RetryPolicy {
+        fn default() -> Self {
+            RetryPolicy::new(3, time::Duration::from_millis(100), time::Duration::from_secs(5))
+        }
+    }
+
+    impl RetryPolicy {
+        /// Creates a policy with jitter.
+        pub fn new(max_retries: u32, base_delay: time::Duration, max_delay: time::Duration) -> Self {
+            RetryPolicy { max_retries, base_delay, max_delay, jitter: true, on_retry: None }
+        }
+
+        /// A policy never retrying.
+        pub fn none() -> Self {
+            RetryPolicy { max_retries: 0, ..RetryPolicy::default() }
+        }
+
+        /// Returns the delay before the retry number `attempt`, starting from 0.
+        pub fn delay_for(&self, attempt: u32) -> time::Duration {
+            let delay = self.base_delay
+                .checked_mul(2u32.saturating_pow(attempt))
+                .map_or(self.max_delay, |d| cmp::min(d, self.max_delay));
+
+            if self.jitter {
+                delay.mul_f64(rand::Rng::gen_range(&mut rand::thread_rng(), 0.5..=1.0))
+            } else {
+                delay
+            }
+        }
+    }
+
+    /// Maximum number of requests sent concurrently by the batch methods.
+    const MAX_CONCURRENT_REQUESTS: usize = 8;
+
+    /// Activities received for criteria selections, keyed by their sorted query parameters. Stale
+    /// entries are removed when read, and all of them when a new entry is inserted.
+    #[derive(fmt::Debug)]
+    pub(crate) struct ResponseCache {
+        pub(crate) ttl: time::Duration,
+        pub(crate) entries: sync::Mutex<collections::HashMap<String, (time::Instant, Activity)>>,
+    }
+
+    impl ResponseCache {
+        pub(crate) fn get(&self, sel: &CriteriaSelection) -> Option<Activity> {
+            let mut entries = self.entries.lock().unwrap();
+            let key = sel.to_string();
+
+            match entries.get(&key) {
+                Some((stored, activity)) if stored.elapsed() < self.ttl => Some(activity.clone()),
+                Some(_) => {
+                    entries.remove(&key);
+                    None
+                }
+                None => None,
+            }
+        }
+
+        pub(crate) fn insert(&self, sel: &CriteriaSelection, activity: &Activity) {
+            let mut entries = self.entries.lock().unwrap();
+            entries.retain(|_, (stored, _)| stored.elapsed() < self.ttl);
+            entries.insert(sel.to_string(), (time::Instant::now(), activity.clone()));
+        }
+    }
+
+    /// Spaces requests evenly so that no more than the configured number is sent per second.
+    #[cfg(feature = "reqwest")]
+    #[derive(fmt::Debug)]
+    struct RateLimiter {
+        interval: time::Duration,
+        next: tokio::sync::Mutex<time::Instant>,
+    }
+
+    #[cfg(feature = "reqwest")]
+    impl RateLimiter {
+        /// Waits until the next request may be sent.
+        async fn acquire(&self) {
+            let mut next = self.next.lock().await;
+            let now = time::Instant::now();
+
+            if *next > now {
+                tokio::time::delay_for(*next - now).await;
+            }
+
+            *next = cmp::max(*next, now) + self.interval;
+        }
+    }
+
+    /// Sends the HTTP requests of a [`BoredApi`], returning the JSON body of the response. Implemented
+    /// by `reqwest::Client`, which is used unless another transport is set with
+    /// [`BoredApi::with_transport`].
+    #[cfg(feature = "reqwest")]
+    pub trait Transport: fmt::Debug + Send + Sync {
+        fn fetch<'a>(&'a self,
+                     url: &'a str,
+                     params: &'a collections::HashMap<String, String>) -> future::BoxFuture<'a, Result<serde_json::Value, Error>>;
+
+        /// Like [`Transport::fetch`], but also returns the response headers. By default, no headers
+        /// are returned.
+        fn fetch_with_headers<'a>(&'a self,
+                                  url: &'a str,
+                                  params: &'a collections::HashMap<String, String>) -> future::BoxFuture<'a, Result<Response, Error>> {
+            Box::pin(async move { Ok((self.fetch(url, params).await?, reqwest::header::HeaderMap::new())) })
+        }
+
+        /// Sends a request without criteria, succeeding only on a 2xx response, whatever its body. By
+        /// default, the request is sent with [`Transport::fetch`] and succeeds if the body is JSON.
+        fn ping<'a>(&'a self, url: &'a str) -> future::BoxFuture<'a, Result<(), Error>> {
+            Box::pin(async move { self.fetch(url, &collections::HashMap::new()).await.map(|_| ()) })
+        }
+    }
+
+    /// The JSON body and the headers of a response.
+    #[cfg(feature = "reqwest")]
+    pub type Response = (serde_json::Value, reqwest::header::HeaderMap);
+
+    /// How much of an unexpected response body is kept in [`Error::UnexpectedBody`].
+    const SNIPPET_LENGTH: usize = 200;
+
+    /// Parses a response body as JSON, shared by all the clients.
+    pub(crate) fn parse_body(content_type: Option<&str>, body: &str) -> Result<serde_json::Value, Error> {
+        serde_json::from_str(body).map_err(|_| Error::UnexpectedBody {
+            content_type: content_type.map(str::to_string),
+            snippet: body.chars().take(SNIPPET_LENGTH).collect(),
+        })
+    }
+
+    /// Returns the `Content-Type` of a response.
+    #[cfg(feature = "reqwest")]
+    pub(crate) fn content_type(headers: &reqwest::header::HeaderMap) -> Option<&str> {
+        headers.get(reqwest::header::CONTENT_TYPE).and_then(|v| v.to_str().ok())
+    }
+
+    #[cfg(feature = "reqwest")]
+    impl Transport for reqwest::Client {
+        fn fetch<'a>(&'a self,
+                     url: &'a str,
+                     params: &'a collections::HashMap<String, String>) -> future::BoxFuture<'a, Result<serde_json::Value, Error>> {
+            Box::pin(async move { Ok(self.fetch_with_headers(url, params).await?.0) })
+        }
+
+        fn fetch_with_headers<'a>(&'a self,
+                                  url: &'a str,
+                                  params: &'a collections::HashMap<String, String>) -> future::BoxFuture<'a, Result<Response, Error>> {
+            Box::pin(async move {
+                let mut r = self.get(url).query(params).send().await?;
+
+                // Other error responses carry an `error` field parsed later.
+                if r.status().is_server_error() || r.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    r = r.error_for_status()?;
+                }
+
+                let headers = r.headers().clone();
+                Ok((parse_body(content_type(&headers), &r.text().await?)?, headers))
+            })
+        }
+
+        fn ping<'a>(&'a self, url: &'a str) -> future::BoxFuture<'a, Result<(), Error>> {
+            Box::pin(async move {
+                self.get(url).send().await?.error_for_status()?;
+                Ok(())
+            })
+        }
+    }
+
+    /// Cancels the operations it is passed to, such as [`BoredApi::random_many_cancellable`].
+    /// Clones share the cancellation.
+    #[cfg(feature = "reqwest")]
+    #[derive(fmt::Debug, Clone)]
+    pub struct CancellationToken {
+        tx: sync::Arc<tokio::sync::watch::Sender<bool>>,
+        rx: tokio::sync::watch::Receiver<bool>,
+    }
+
+    #[cfg(feature = "reqwest")]
+    impl Default for CancellationToken {
+        fn default() -> Self {
+            let (tx, rx) = tokio::sync::watch::channel(false);
+            CancellationToken { tx: sync::Arc::new(tx), rx }
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    impl CancellationToken {
+        pub fn new() -> Self {
+            CancellationToken::default()
+        }
+
+        /// Makes the pending operations fail with [`Error::Cancelled`], dropping their requests.
+        pub fn cancel(&self) {
+            let _ = self.tx.broadcast(true);
+        }
+
+        pub fn is_cancelled(&self) -> bool {
+            *self.rx.borrow()
+        }
+
+        /// Completes once the token is cancelled.
+        async fn cancelled(&self) {
+            let mut rx = self.rx.clone();
+
+            while let Some(cancelled) = rx.recv().await {
+                if cancelled {
+                    return;
+                }
+            }
+        }
+
+        /// Runs `f` unless the token gets cancelled first.
+        async fn run<F: future::Future>(&self, f: F) -> Result<F::Output, Error> {
+            if self.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+
+            let cancelled = self.cancelled();
+            futures::pin_mut!(f, cancelled);
+
+            match future::select(f, cancelled).await {
+                future::Either::Left((output, _)) => Ok(output),
+                future::Either::Right(_) => Err(Error::Cancelled),
+            }
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[derive(fmt::Debug)]
+    pub struct BoredApi {
+        pub url: String,
+        pub client: reqwest::Client,
+        retry: Option<RetryPolicy>,
+        cache: Option<sync::Arc<ResponseCache>>,
+        backend: Backend,
+        rate_limiter: Option<sync::Arc<RateLimiter>>,
+        parse_mode: ParseMode,
+        fallbacks: Vec<String>,
+        next_mirror: Option<sync::Arc<sync::atomic::AtomicUsize>>,
+        transport: Option<sync::Arc<dyn Transport>>,
+        #[cfg(feature = "serde")]
+        file_cache: Option<sync::Arc<FileCache>>,
+    }
+
+    /// The endpoint used by [`BoredApi::default`].
+    pub const DEFAULT_URL: &str = "https://www.boredapi.com/api/activity";
+
+    /// The environment variable read by [`BoredApi::from_env`] for the endpoint.
+    pub const URL_ENV_VAR: &str = "BORED_API_URL";
+
+    /// The environment variable read by [`BoredApi::from_env`] for the timeout in milliseconds.
+    pub const TIMEOUT_ENV_VAR: &str = "BORED_API_TIMEOUT_MS";
+
+    /// The endpoint used by [`BoredApi::appbrewery`].
+    pub const APPBREWERY_URL: &str = "https://bored-api.appbrewery.com/random";
+
+    #[cfg(feature = "reqwest")]
+    impl Default for BoredApi {
+        fn default() -> Self {
+            BoredApi::builder().build().expect("failed to build reqwest client")
+        }
+    }
+
+    /// Configures a [`BoredApi`] built with a single `reqwest::Client`.
+    #[cfg(feature = "reqwest")]
+    #[derive(fmt::Debug)]
+    pub struct BoredApiBuilder {
+        url: String,
+        client: reqwest::ClientBuilder,
+        retry: Option<RetryPolicy>,
+        language: Option<String>,
+    }
+
+    #[cfg(feature = "reqwest")]
+    impl Default for BoredApiBuilder {
+        fn default() -> Self {
+            BoredApiBuilder { url: DEFAULT_URL.to_string(), client: reqwest::Client::builder(), retry: None, language: None }
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    impl BoredApiBuilder {
+        /// Sets the endpoint, [`DEFAULT_URL`] by default.
+        pub fn url(mut self, url: impl Into<String>) -> Self {
+            self.url = url.into();
+            self
+        }
+
+        /// Makes requests fail with [`Error::Timeout`] if they don't complete within `timeout`.
+        pub fn timeout(mut self, timeout: time::Duration) -> Self {
+            self.client = self.client.timeout(timeout);
+            self
+        }
+
+        /// Makes requests fail with [`Error::Timeout`] if the connection isn't established within
+        /// `timeout`.
+        pub fn connect_timeout(mut self, timeout: time::Duration) -> Self {
+            self.client = self.client.connect_timeout(timeout);
+            self
+        }
+
+        /// Sets the `User-Agent` header.
+        pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+            self.client = self.client.user_agent(user_agent.into());
+            self
+        }
+
+        /// Sends the given headers with every request.
+        pub fn headers(mut self, headers: reqwest::header::HeaderMap) -> Self {
+            self.client = self.client.default_headers(headers);
+            self
+        }
+
+        /// Sets the `Accept-Language` header, asking for descriptions in the given language if the
+        /// endpoint supports it.
+        pub fn language(mut self, language: impl Into<String>) -> Self {
+            self.language = Some(language.into());
+            self
+        }
+
+        /// Sends requests through the given proxy.
+        pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+            self.client = self.client.proxy(proxy);
+            self
+        }
+
+        /// Whether to accept gzip and brotli compressed responses, enabled by default.
+        #[cfg(feature = "compression")]
+        pub fn compression(mut self, enabled: bool) -> Self {
+            self.client = self.client.gzip(enabled).brotli(enabled);
+            self
+        }
+
+        /// Retries failed requests according to `policy`.
+        pub fn retry(mut self, policy: RetryPolicy) -> Self {
+            self.retry = Some(policy);
+            self
+        }
+
+        /// Builds the wrapper, failing if the `reqwest::Client` can't be built.
+        pub fn build(self) -> Result<BoredApi, Error> {
+            let mut client = self.client;
+
+            if let Some(language) = self.language {
+                let value = reqwest::header::HeaderValue::from_str(&language)
+                    .map_err(|_| Error::InvalidHeader { name: "Accept-Language", value: language.clone() })?;
+                let mut headers = reqwest::header::HeaderMap::new();
+                headers.insert(reqwest::header::ACCEPT_LANGUAGE, value);
+                client = client.default_headers(headers);
+            }
+
+            let mut api = BoredApi::from_client(self.url, client.build()?);
+            api.retry = self.retry;
+            Ok(api)
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    impl Clone for BoredApi {
+        fn clone(&self) -> Self {
+            BoredApi {
+                url: self.url.clone(),
+                client: self.client.clone(),
+                retry: self.retry,
+                cache: self.cache.clone(),
+                backend: self.backend,
+                rate_limiter: self.rate_limiter.clone(),
+                parse_mode: self.parse_mode,
+                fallbacks: self.fallbacks.clone(),
+                next_mirror: self.next_mirror.clone(),
+                transport: self.transport.clone(),
+                #[cfg(feature = "serde")]
+                file_cache: self.file_cache.clone(),
+            }
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    impl BoredApi {
+        /// Returns a builder configuring several options of the wrapper at once.
+        pub fn builder() -> BoredApiBuilder {
+            BoredApiBuilder::default()
+        }
+
+        /// Returns the client sending the requests, which can be reused for other requests.
+        pub fn client(&self) -> &reqwest::Client {
+            &self.client
+        }
+
+        /// Creates an API wrapper sending requests to the given endpoint with the default client.
+        pub fn with_url(url: impl Into<String>) -> Self {
+            BoredApi::builder().url(url).build().expect("failed to build reqwest client")
+        }
+
+        /// Creates an API wrapper sending requests to `primary`, then to each of `fallbacks` in order
+        /// while the requests fail because of HTTP errors or timeouts.
+        pub fn with_fallbacks(primary: String, fallbacks: Vec<String>) -> Self {
+            BoredApi { fallbacks, ..BoredApi::with_url(primary) }
+        }
+
+        /// Creates an API wrapper spreading the requests across `urls` in turn. A request failing
+        /// because of HTTP errors or timeouts is sent to the next mirror, like with
+        /// [`BoredApi::with_fallbacks`]. Clones of the wrapper share the turn.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `urls` is empty.
+        pub fn with_mirrors(mut urls: Vec<String>) -> Self {
+            assert!(!urls.is_empty(), "no mirrors given");
+
+            let primary = urls.remove(0);
+            BoredApi { next_mirror: Some(Default::default()), ..BoredApi::with_fallbacks(primary, urls) }
+        }
+
+        /// Creates an API wrapper sending requests to the given endpoint with a preconfigured client.
+        pub fn with_client(url: impl Into<String>, client: reqwest::Client) -> Self {
+            BoredApi::from_client(url.into(), client)
+        }
+
+        /// Creates an API wrapper configured by the environment: the endpoint is read from
+        /// `BORED_API_URL`, [`DEFAULT_URL`] if unset, and the timeout in milliseconds from
+        /// `BORED_API_TIMEOUT_MS`, none if unset.
+        pub fn from_env() -> Result<Self, Error> {
+            let var = |name| std::env::var(name).ok().filter(|v: &String| !v.is_empty());
+            let mut builder = BoredApi::builder();
+
+            if let Some(url) = var(URL_ENV_VAR) {
+                url::Url::parse(&url).map_err(|_| Error::InvalidEnvVar { name: URL_ENV_VAR, value: url.clone() })?;
+                builder = builder.url(url);
+            }
+
+            if let Some(timeout) = var(TIMEOUT_ENV_VAR) {
+                let millis = timeout.parse().map_err(|_| Error::InvalidEnvVar { name: TIMEOUT_ENV_VAR, value: timeout })?;
+                builder = builder.timeout(time::Duration::from_millis(millis));
+            }
+
+            builder.build()
+        }
+
+        /// Creates an API wrapper for the community mirror at bored-api.appbrewery.com.
+        pub fn appbrewery() -> Self {
+            BoredApi { backend: Backend::AppBrewery, ..BoredApi::with_url(APPBREWERY_URL) }
+        }
+
+        /// Creates an API wrapper whose requests fail with [`Error::Timeout`] if they don't complete
+        /// within `timeout`. Wrappers created otherwise have no timeout.
+        pub fn with_timeout(timeout: time::Duration) -> Self {
+            BoredApi::builder().timeout(timeout).build().expect("failed to build reqwest client")
+        }
+
+        /// Creates an API wrapper whose requests fail with [`Error::Timeout`] if the connection isn't
+        /// established within `timeout`, however long the response takes afterwards.
+        pub fn with_connect_timeout(timeout: time::Duration) -> Self {
+            BoredApi::builder().connect_timeout(timeout).build().expect("failed to build reqwest client")
+        }
+
+        /// Creates an API wrapper sending the given `User-Agent` header.
+        pub fn with_user_agent(user_agent: impl Into<String>) -> Self {
+            BoredApi::builder().user_agent(user_agent).build().expect("failed to build reqwest client")
+        }
+
+        /// Creates an API wrapper accepting compressed responses or not.
+        #[cfg(feature = "compression")]
+        pub fn with_compression(enabled: bool) -> Self {
+            BoredApi::builder().compression(enabled).build().expect("failed to build reqwest client")
+        }
+
+        /// Creates an API wrapper sending the given `Accept-Language` header.
+        pub fn with_language(language: impl Into<String>) -> Self {
+            BoredApi::builder().language(language).build().expect("failed to build reqwest client")
+        }
+
+        /// Creates an API wrapper sending the given headers with every request. Header names and
+        /// values are validated when the `HeaderMap` is built.
+        pub fn with_headers(headers: reqwest::header::HeaderMap) -> Self {
+            BoredApi::builder().headers(headers).build().expect("failed to build reqwest client")
+        }
+
+        /// Creates an API wrapper sending requests through the given proxy.
+        pub fn with_proxy(proxy: reqwest::Proxy) -> Result<Self, Error> {
+            BoredApi::builder().proxy(proxy).build()
+        }
+
+        /// Creates an API wrapper sending all requests through the proxy at `proxy_url`. An invalid
+        /// URL is reported as [`Error::HttpError`].
+        pub fn with_proxy_url(proxy_url: &str) -> Result<Self, Error> {
+            BoredApi::with_proxy(reqwest::Proxy::all(proxy_url)?)
+        }
+
+        fn from_client(url: String, client: reqwest::Client) -> Self {
+            BoredApi {
+                url,
+                client,
+                retry: None,
+                cache: None,
+                backend: Backend::Legacy,
+                rate_limiter: None,
+                parse_mode: ParseMode::default(),
+                fallbacks: Vec::new(),
+                next_mirror: None,
+                transport: None,
+                #[cfg(feature = "serde")]
+                file_cache: None,
+            }
+        }
+
+        /// Makes the wrapper send its requests through `transport` instead of [`BoredApi::client`].
+        pub fn with_transport(mut self, transport: impl Transport + 'static) -> Self {
+            self.transport = Some(sync::Arc::new(transport));
+            self
+        }
+
+        /// Makes the wrapper retry failed requests according to `policy`.
+        pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+            self.retry = Some(policy);
+            self
+        }
+
+        /// Sets how responses deviating from the documented shape are handled,
+        /// [`ParseMode::Lenient`] by default.
+        pub fn with_parse_mode(mut self, mode: ParseMode) -> Self {
+            self.parse_mode = mode;
+            self
+        }
+
+        /// Limits the wrapper to sending at most `max_per_second` requests per second, including
+        /// retries. Clones of the wrapper share the limit.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `max_per_second` is 0.
+        pub fn with_rate_limit(mut self, max_per_second: u32) -> Self {
+            assert!(max_per_second > 0, "rate limit must be positive");
+
+            self.rate_limiter = Some(sync::Arc::new(RateLimiter {
+                interval: time::Duration::from_secs(1) / max_per_second,
+                next: tokio::sync::Mutex::new(time::Instant::now()),
+            }));
+            self
+        }
+
+        /// Makes [`by_criteria`](BoredApi::by_criteria) reuse the activity received for the same
+        /// criteria during the last `ttl`. Queries without criteria, such as
+        /// [`random`](BoredApi::random), are never cached. Clones of the wrapper share the cache.
+        pub fn with_cache(mut self, ttl: time::Duration) -> Self {
+            self.cache = Some(sync::Arc::new(ResponseCache { ttl, entries: Default::default() }));
+            self
+        }
+
+        /// Makes [`by_criteria`](BoredApi::by_criteria) reuse the activities stored in `cache` for
+        /// the same criteria, storing the new ones there. Like with
+        /// [`with_cache`](BoredApi::with_cache), queries without criteria are never cached.
+        #[cfg(feature = "serde")]
+        pub fn with_file_cache(mut self, cache: FileCache) -> Self {
+            self.file_cache = Some(sync::Arc::new(cache));
+            self
+        }
+
+        pub async fn random(&self) -> Result<Activity, Error> {
+            self.by_criteria(Ok).await
+        }
+
+        pub async fn by_criteria<F>(&self, selection: F) -> Result<Activity, Error>
+            where F: FnOnce(CriteriaSelection) -> Result<CriteriaSelection, Error> {
+            let sel = selection(CriteriaSelection::default())?;
+            let cache = self.cache.as_ref().filter(|_| !sel.parameters().is_empty());
+
+            if let Some(activity) = cache.and_then(|c| c.get(&sel)) {
+                return Ok(activity);
+            }
+
+            #[cfg(feature = "serde")]
+            let file_cache = self.file_cache.as_ref().filter(|_| !sel.parameters().is_empty());
+
+            #[cfg(feature = "serde")]
+            if let Some(activity) = file_cache.and_then(|c| c.get(&sel)) {
+                return Ok(activity);
+            }
+
+            #[cfg(feature = "logging")]
+            log::debug!("GET {}?{}", self.url, sel);
+
+            let result = self.fetch(&sel).await;
+
+            #[cfg(feature = "logging")]
+            match &result {
+                Ok(activity) => log::info!("fetched activity {}", activity.key),
+                Err(e) => log::warn!("request to {} failed: {}", self.url, e),
+            }
+
+            let activity = result?;
+
+            if let Some(c) = cache {
+                c.insert(&sel, &activity);
+            }
+
+            #[cfg(feature = "serde")]
+            if let Some(c) = file_cache {
+                c.insert(&sel, &activity);
+            }
+
+            Ok(activity)
+        }
+
+        /// Checks that the endpoint is reachable by requesting a random activity, succeeding only on
+        /// a 2xx response. The response body is ignored.
+        pub async fn ping(&self) -> Result<(), Error> {
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire().await;
+            }
+
+            match &self.transport {
+                Some(transport) => transport.ping(&self.url).await,
+                None => self.client.ping(&self.url).await,
+            }
+        }
+
+        /// Returns the URL [`BoredApi::by_criteria`] would request, with the query parameters sorted
+        /// by name and appended to the query of the endpoint, without sending anything.
+        pub fn build_url<F>(&self, selection: F) -> Result<url::Url, Error>
+            where F: FnOnce(CriteriaSelection) -> Result<CriteriaSelection, Error> {
+            let sel = selection(CriteriaSelection::default())?;
+            sel.validate()?;
+            let mut url = url::Url::parse(&self.url)?;
+
+            if !sel.parameters().is_empty() {
+                let mut parameters: Vec<_> = sel.parameters().iter().collect();
+                parameters.sort();
+                url.query_pairs_mut().extend_pairs(parameters);
+            }
+
+            Ok(url)
+        }
+
+        /// Like [`BoredApi::random`], but also returns the time taken by the request, including
+        /// parsing the response.
+        pub async fn random_timed(&self) -> Result<(Activity, time::Duration), Error> {
+            self.by_criteria_timed(Ok).await
+        }
+
+        /// Like [`BoredApi::by_criteria`], but also returns the time taken by the request,
+        /// including parsing the response.
+        pub async fn by_criteria_timed<F>(&self, selection: F) -> Result<(Activity, time::Duration), Error>
+            where F: FnOnce(CriteriaSelection) -> Result<CriteriaSelection, Error> {
+            let start = time::Instant::now();
+            let activity = self.by_criteria(selection).await?;
+            Ok((activity, start.elapsed()))
+        }
+
+        /// Fetches a random activity matching the given query.
+        pub async fn query(&self, query: ActivityQuery) -> Result<Activity, Error> {
+            self.by_criteria(|_| CriteriaSelection::try_from(query)).await
+        }
+
+        /// Like [`BoredApi::random`], but returns `None` instead of [`Error::NoActivityFound`].
+        pub async fn try_random(&self) -> Result<Option<Activity>, Error> {
+            self.try_by_criteria(Ok).await
+        }
+
+        /// Like [`BoredApi::by_criteria`], but returns `None` instead of [`Error::NoActivityFound`].
+        pub async fn try_by_criteria<F>(&self, selection: F) -> Result<Option<Activity>, Error>
+            where F: FnOnce(CriteriaSelection) -> Result<CriteriaSelection, Error> {
+            match self.by_criteria(selection).await {
+                Ok(activity) => Ok(Some(activity)),
+                Err(Error::NoActivityFound) => Ok(None),
+                Err(e) => Err(e),
+            }
+        }
+
+        /// Fetches random activities until one satisfies `predicate`, giving up with
+        /// [`Error::ExhaustedAttempts`] after `max_attempts` activities.
+        pub async fn random_where<P>(&self, max_attempts: usize, predicate: P) -> Result<Activity, Error>
+            where P: Fn(&Activity) -> bool {
+            for _ in 0..max_attempts {
+                let activity = self.random().await?;
+
+                if predicate(&activity) {
+                    return Ok(activity);
+                }
+            }
+
+            Err(Error::ExhaustedAttempts { attempts: max_attempts })
+        }
+
+        /// Fetches a random activity that costs nothing. The price is also checked locally: if the
+        /// API ignores the filter twice, fails with [`Error::NoActivityFound`].
+        pub async fn random_free(&self) -> Result<Activity, Error> {
+            for _ in 0..2 {
+                let activity = self.by_criteria(|s| s.set(MAX_PRICE, 0.0)).await?;
+
+                if activity.is_free() {
+                    return Ok(activity);
+                }
+            }
+
+            Err(Error::NoActivityFound)
+        }
+
+        /// Fetches a random activity that is easy to do, i.e. whose accessibility is at most 0.25.
+        ///
+        /// Note that the accessibility scale is inverted: 0 is the most accessible and 1 the least.
+        pub async fn random_accessible(&self) -> Result<Activity, Error> {
+            self.by_criteria(|s| s.set(MAX_ACCESSIBILITY, 0.25)).await
+        }
+
+        /// Fetches a random activity of the given type.
+        pub async fn by_type(&self, activity_type: ActivityType) -> Result<Activity, Error> {
+            self.by_criteria(|s| s.set(TYPE, activity_type)).await
+        }
+
+        /// Fetches a random activity for the given number of participants.
+        pub async fn by_participants(&self, participants: u64) -> Result<Activity, Error> {
+            self.by_criteria(|s| s.set(PARTICIPANTS, participants)).await
+        }
+
+        /// Fetches a random activity with exactly the given price.
+        pub async fn by_price(&self, price: f64) -> Result<Activity, Error> {
+            self.by_criteria(|s| s.set(EXACT_PRICE, price)).await
+        }
+
+        /// Fetches a random activity with exactly the given accessibility.
+        pub async fn by_accessibility(&self, accessibility: f64) -> Result<Activity, Error> {
+            self.by_criteria(|s| s.set(EXACT_ACCESSIBILITY, accessibility)).await
+        }
+
+        /// Fetches the activity with the given 7-digit key.
+        pub async fn by_key(&self, key: u64) -> Result<Activity, Error> {
+            let activity = self.by_criteria(|s| s.set(KEY, key)).await?;
+
+            if activity.key != key {
+                return Err(Error::BadResponse);
+            }
+
+            Ok(activity)
+        }
+
+        /// Like [`random`](BoredApi::random), but also returns the JSON received from the API.
+        pub async fn random_raw(&self) -> Result<(Activity, serde_json::Value), Error> {
+            self.by_criteria_raw(Ok).await
+        }
+
+        /// Like [`by_criteria`](BoredApi::by_criteria), but also returns the JSON received from the
+        /// API. A response that can't be parsed is reported as [`Error::UnparsedResponse`], holding
+        /// the JSON; the errors sent by the API are returned as they are. The cache is bypassed.
+        pub async fn by_criteria_raw<F>(&self, selection: F) -> Result<(Activity, serde_json::Value), Error>
+            where F: FnOnce(CriteriaSelection) -> Result<CriteriaSelection, Error> {
+            let sel = selection(CriteriaSelection::default())?;
+            let (json, _) = self.fetch_json(&sel).await?;
+
+            match parse_activity(json.clone(), self.backend, self.parse_mode) {
+                Ok(activity) => Ok((activity, json)),
+                Err(e @ Error::NoActivityFound) | Err(e @ Error::ApiError(_)) => Err(e),
+                Err(e) => Err(Error::UnparsedResponse { raw: json, error: Box::new(e) }),
+            }
+        }
+
+        /// Like [`BoredApi::random`], but also returns the response headers, e.g. to read
+        /// `Retry-After`.
+        pub async fn random_with_headers(&self) -> Result<(Activity, reqwest::header::HeaderMap), Error> {
+            let (json, headers) = self.fetch_json(&CriteriaSelection::default()).await?;
+            Ok((parse_activity(json, self.backend, self.parse_mode)?, headers))
+        }
+
+        /// Returns an endless stream of random activities. A request is sent only when the next item
+        /// is polled.
+        pub fn random_stream(&self) -> impl Stream<Item=Result<Activity, Error>> + '_ {
+            stream::unfold((), move |_| async move { Some((self.random().await, ())) })
+        }
+
+        /// Fetches `count` random activities, sending up to 8 requests at a time, and sends the
+        /// results into `tx` as they arrive. Stops early if the receiver is dropped.
+        pub async fn stream_into(&self, mut tx: tokio::sync::mpsc::Sender<Result<Activity, Error>>, count: usize) {
+            use futures::StreamExt;
+
+            let mut results = stream::iter(0..count)
+                .map(|_| self.random())
+                .buffer_unordered(MAX_CONCURRENT_REQUESTS);
+
+            while let Some(r) = results.next().await {
+                if tx.send(r).await.is_err() {
+                    break;
+                }
+            }
+        }
+
+        /// Fetches `n` random activities, sending up to 8 requests at a time.
+        ///
+        /// Returns the first error encountered if any of the requests fails.
+        pub async fn random_many(&self, n: usize) -> Result<Vec<Activity>, Error> {
+            self.by_criteria_many(n, Ok).await
+        }
+
+        /// Fetches random activities until `n` distinct ones are received, sending up to 8 requests
+        /// at a time. Fails with [`Error::ExhaustedAttempts`] if they aren't received within
+        /// `max_attempts` requests.
+        pub async fn collect_unique(&self, n: usize, max_attempts: usize) -> Result<Vec<Activity>, Error> {
+            self.collect_unique_cancellable(n, max_attempts, &CancellationToken::new()).await
+        }
+
+        /// Like [`BoredApi::collect_unique`], but fails with [`Error::Cancelled`] as soon as `token`
+        /// is cancelled.
+        pub async fn collect_unique_cancellable(&self,
+                                                n: usize,
+                                                max_attempts: usize,
+                                                token: &CancellationToken) -> Result<Vec<Activity>, Error> {
+            let sel = CriteriaSelection::default();
+            let mut activities = Vec::with_capacity(n);
+            let mut keys = collections::HashSet::new();
+            let mut attempts = 0;
+
+            while activities.len() < n && attempts < max_attempts {
+                let batch = cmp::min(cmp::min(n - activities.len(), max_attempts - attempts), MAX_CONCURRENT_REQUESTS);
+                attempts += batch;
+
+                for r in token.run(future::join_all((0..batch).map(|_| self.fetch(&sel)))).await? {
+                    let activity = r?;
+
+                    if activities.len() < n && keys.insert(activity.key) {
+                        activities.push(activity);
+                    }
+                }
+            }
+
+            if activities.len() < n {
+                return Err(Error::ExhaustedAttempts { attempts });
+            }
+
+            Ok(activities)
+        }
+
+        /// Fetches `n` activities matching the selection, sending up to 8 requests at a time.
+        ///
+        /// Returns the first error encountered if any of the requests fails.
+        #[cfg_attr(feature = "tracing", tracing::instrument(skip(self, selection)))]
+        pub async fn by_criteria_many<F>(&self, n: usize, selection: F) -> Result<Vec<Activity>, Error>
+            where F: FnOnce(CriteriaSelection) -> Result<CriteriaSelection, Error> {
+            let sel = selection(CriteriaSelection::default())?;
+            self.fetch_many(&sel, n, &|_, _| {}, &CancellationToken::new()).await
+        }
+
+        /// Like [`BoredApi::random_many`], but fails with [`Error::Cancelled`] as soon as `token` is
+        /// cancelled.
+        pub async fn random_many_cancellable(&self, n: usize, token: &CancellationToken) -> Result<Vec<Activity>, Error> {
+            self.fetch_many(&CriteriaSelection::default(), n, &|_, _| {}, token).await
+        }
+
+        /// Like [`BoredApi::random_many`], but calls `on_progress` with the number of completed
+        /// requests and `n` as each request completes.
+        pub async fn random_many_with_progress<P>(&self, n: usize, on_progress: P) -> Result<Vec<Activity>, Error>
+            where P: Fn(usize, usize) + Send + Sync {
+            self.fetch_many(&CriteriaSelection::default(), n, &on_progress, &CancellationToken::new()).await
+        }
+
+        async fn fetch_many(&self,
+                            sel: &CriteriaSelection,
+                            n: usize,
+                            on_progress: &(dyn Fn(usize, usize) + Send + Sync),
+                            token: &CancellationToken) -> Result<Vec<Activity>, Error> {
+            let completed = sync::atomic::AtomicUsize::new(0);
+            let mut activities = Vec::with_capacity(n);
+
+            for start in (0..n).step_by(MAX_CONCURRENT_REQUESTS) {
+                let end = cmp::min(n, start + MAX_CONCURRENT_REQUESTS);
+                let requests = (start..end).map(|_| async {
+                    let r = self.fetch(sel).await;
+                    on_progress(completed.fetch_add(1, sync::atomic::Ordering::SeqCst) + 1, n);
+                    r
+                });
+
+                for r in token.run(future::join_all(requests)).await? {
+                    activities.push(r?);
+                }
+            }
+
+            Ok(activities)
+        }
+
+        /// Sends a request for an activity. With the `tracing` feature, every request gets a span
+        /// recording the query, the time taken and the outcome, but none of the headers.
+        #[cfg_attr(feature = "tracing", tracing::instrument(
+            name = "request",
+            skip(self, sel),
+            fields(url = %self.url, query = %sel, elapsed_ms = tracing::field::Empty, outcome = tracing::field::Empty),
+        ))]
+        async fn fetch(&self, sel: &CriteriaSelection) -> Result<Activity, Error> {
+            #[cfg(feature = "tracing")]
+            let start = time::Instant::now();
+
+            let result = match self.fetch_json(sel).await {
+                Ok((json, _)) => parse_activity(json, self.backend, self.parse_mode),
+                Err(e) => Err(e),
+            };
+
+            #[cfg(feature = "tracing")]
+            {
+                let span = tracing::Span::current();
+                span.record("elapsed_ms", start.elapsed().as_millis() as u64);
+                span.record("outcome", if result.is_ok() { "ok" } else { "error" });
+            }
+
+            result
+        }
+
+        /// Sends the request to the endpoint, then to the fallbacks while it fails because of the
+        /// transport, returning the last error if none succeeds. With mirrors, every request starts
+        /// from the next one.
+        async fn fetch_json(&self, sel: &CriteriaSelection) -> Result<Response, Error> {
+            sel.validate()?;
+
+            let mut urls: Vec<_> = std::iter::once(&self.url).chain(&self.fallbacks).collect();
+
+            if let Some(next) = &self.next_mirror {
+                let len = urls.len();
+                urls.rotate_left(next.fetch_add(1, sync::atomic::Ordering::Relaxed) % len);
+            }
+
+            let mut result = self.fetch_json_from(urls[0], sel).await;
+
+            for url in &urls[1..] {
+                match result {
+                    Err(Error::HttpError(_)) | Err(Error::Transport { .. }) | Err(Error::Timeout) =>
+                        result = self.fetch_json_from(url, sel).await,
+                    _ => break,
+                }
+            }
+
+            result
+        }
+
+        /// Sends the request, retrying it according to the retry policy.
+        async fn fetch_json_from(&self, url: &str, sel: &CriteriaSelection) -> Result<Response, Error> {
+            let mut retry = 0;
+
+            loop {
+                match (self.send(url, sel).await, self.retry) {
+                    (Err(e), Some(policy)) if e.is_retryable() && retry < policy.max_retries => {
+                        retry += 1;
+
+                        if let Some(on_retry) = policy.on_retry {
+                            on_retry(retry, &e);
+                        }
+
+                        tokio::time::delay_for(policy.delay_for(retry - 1)).await;
+                    }
+                    (r, _) => return r,
+                }
+            }
+        }
+
+        async fn send(&self, url: &str, sel: &CriteriaSelection) -> Result<Response, Error> {
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire().await;
+            }
+
+            match &self.transport {
+                Some(transport) => transport.fetch_with_headers(url, sel.parameters()).await,
+                None => self.client.fetch_with_headers(url, sel.parameters()).await,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+pub mod blocking;
+
+#[cfg(feature = "serde")]
+mod cache;
+
+#[cfg(feature = "serde")]
+pub use crate::cache::FileCache;
+
+#[cfg(feature = "ureq")]
+mod ureq_client;
+
+#[cfg(feature = "ureq")]
+pub use crate::ureq_client::BoredApiUreq;
+
+pub mod util;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(all(test, feature = "reqwest"))]
+mod tests {
+    use crate::boredapi;
+    use crate::mock::{MockResponse, MockServer};
+    use tokio::runtime::Runtime;
+    use crate::boredapi::{Error, Activity};
+    use std::error::Error as _;
+
+    fn sample(key: u64, price: f64, accessibility: f64) -> Activity {
+        Activity::new(format!("Activity {}", key),
+                      accessibility,
+                      boredapi::ActivityType::Recreational,
+                      1,
+                      price,
+                      None,
+                      key)
+    }
+
+    macro_rules! aw {
+    ($e:expr) => {
+        Runtime::new().expect("").block_on($e)
+    };
+  }
+
+    #[test]
+    #[ignore = "sends requests to the live API"]
+    fn random() {
+        let api = boredapi::BoredApi::default();
+
+        for _ in 0..2 {
+            match aw!(api.random()) {
+                Ok(a) => { println!("{:?}", a); }
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    #[ignore = "sends requests to the live API"]
+    fn by_criteria() {
+        match aw!(boredapi::BoredApi::default().by_criteria(|sel| sel.set(boredapi::TYPE, boredapi::ActivityType::Busywork))) {
+            Ok(a) => {
+                assert_eq!(a.activity_type, boredapi::ActivityType::Busywork);
+                println!("{:?}", a)
+            }
+            Err(e) => panic!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn no_activity() {
+        let server = MockServer::start(vec![MockResponse::json(serde_json::json!({
+            "error": "No activity found with the specified parameters"
+        }))]);
+        let api = boredapi::BoredApi::with_url(server.url());
+
+        match aw!(api.by_criteria(|s| s.set(boredapi::EXACT_ACCESSIBILITY, 1.0))) {
+            Ok(a) => panic!("{:?}", a),
+            Err(e) => match e {
+                Error::NoActivityFound => {}
+                e => panic!("{:?}", e),
+            },
+        }
+    }
+
+    #[test]
+    fn error_is_std_error() {
+        let _: Box<dyn std::error::Error> = Box::new(Error::BadResponse);
+
+        let e = match aw!(reqwest::Client::new().get("http://127.0.0.1:0/").send()) {
+            Ok(r) => panic!("{:?}", r),
+            Err(e) => Error::HttpError(e),
+        };
+        assert!(e.source().is_some());
+        assert!(Error::BadResponse.source().is_none());
+    }
+
+    #[test]
+    fn error_display() {
+        assert_eq!(Error::ApiError("foo".into()).to_string(), "API error: foo");
+        assert_eq!(Error::BadResponse.to_string(), "malformed or unexpected API response");
+    }
+
+    #[test]
+    fn activity_display() {
+        let a = Activity::new("Learn Express.js".to_string(),
+                              0.25,
+                              boredapi::ActivityType::Education,
+                              1,
+                              0.1,
+                              Some(url::Url::parse("https://expressjs.com/").unwrap()),
+                              3943506);
+        let s = a.to_string();
+        assert!(s.contains("Learn Express.js"));
+        assert!(s.contains("education"));
+        assert!(s.contains("https://expressjs.com/"));
+    }
+
+    #[test]
+    fn activity_type_display() {
+        use boredapi::ActivityType::*;
+
+        assert_eq!(Diy.to_string(), "diy");
+
+        for (t, s) in [(Education, "education"), (Recreational, "recreational"), (Social, "social"),
+            (Diy, "diy"), (Charity, "charity"), (Cooking, "cooking"), (Relaxation, "relaxation"),
+            (Music, "music"), (Busywork, "busywork")] {
+            assert_eq!(t.to_string(), s);
+            assert_eq!(s.parse::<boredapi::ActivityType>().unwrap(), t);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_impls() {
+        fn assert_serde<T: serde::Serialize + serde::de::DeserializeOwned>() {}
+
+        assert_serde::<Activity>();
+        assert_serde::<boredapi::ActivityType>();
+        assert_eq!(serde_json::to_value(boredapi::ActivityType::Diy).unwrap(), "diy");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_activity() {
+        let a: Activity = serde_json::from_value(serde_json::json!({
+            "activity": "Learn Express.js",
+            "accessibility": 0.25,
+            "type": "education",
+            "participants": 1,
+            "price": 0.1,
+            "link": "https://expressjs.com/",
+            "key": "3943506"
+        })).unwrap();
+        assert_eq!(a.description, "Learn Express.js");
+        assert_eq!(a.activity_type, boredapi::ActivityType::Education);
+        assert_eq!(a.link, Some(url::Url::parse("https://expressjs.com/").unwrap()));
+        assert_eq!(a.key, 3943506);
+
+        let a: Activity = serde_json::from_value(serde_json::json!({
+            "activity": "Take a bubble bath",
+            "accessibility": 0.1,
+            "type": "relaxation",
+            "participants": 1,
+            "price": 0.15,
+            "link": "",
+            "key": "4908959"
+        })).unwrap();
+        assert_eq!(a.link, None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_activity() {
+        let json = serde_json::json!({
+            "activity": "Take a bubble bath",
+            "accessibility": 0.1,
+            "type": "relaxation",
+            "participants": 1,
+            "price": 0.15,
+            "link": "",
+            "key": "4908959"
+        });
+        let a: Activity = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&a).unwrap(), json);
+    }
+
+    #[test]
+    fn with_url() {
+        let api = boredapi::BoredApi::with_url(format!("http://{}/api/activity", "localhost:8080"));
+        assert_eq!(api.url, "http://localhost:8080/api/activity");
+        assert_eq!(boredapi::BoredApi::default().url, boredapi::DEFAULT_URL);
+    }
+
+    #[test]
+    fn default_url_is_https() {
+        assert!(boredapi::BoredApi::default().url.starts_with("https://"));
+    }
+
+    #[test]
+    fn timeout() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut api = boredapi::BoredApi::with_timeout(std::time::Duration::from_millis(100));
+        api.url = format!("http://{}/api/activity", listener.local_addr().unwrap());
+
+        match aw!(api.random()) {
+            Err(Error::Timeout) => {}
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn retry() {
+        let server = MockServer::start(vec![
+            MockResponse::status(503),
+            MockResponse::status(503),
+            MockResponse::json(crate::mock::activity_json(3943506)),
+        ]);
+        let policy = boredapi::RetryPolicy::new(3,
+                                                std::time::Duration::from_millis(1),
+                                                std::time::Duration::from_millis(10));
+        let api = boredapi::BoredApi::with_url(server.url()).with_retry(policy);
+
+        assert_eq!(aw!(api.random()).unwrap().key, 3943506);
+        assert_eq!(server.hits(), 3);
+    }
+
+    #[test]
+    fn retry_skips_api_errors() {
+        let server = MockServer::start(vec![
+            MockResponse::json(serde_json::json!({ "error": "Failed to query due to error in arguments" })),
+            MockResponse::json(crate::mock::activity_json(3943506)),
+        ]);
+        let policy = boredapi::RetryPolicy::new(3,
+                                                std::time::Duration::from_millis(1),
+                                                std::time::Duration::from_millis(10));
+        let api = boredapi::BoredApi::with_url(server.url()).with_retry(policy);
+
+        match aw!(api.random()) {
+            Err(Error::ApiError(_)) => {}
+            r => panic!("{:?}", r),
+        }
+        assert_eq!(server.hits(), 1);
+    }
+
+    #[test]
+    fn random_many() {
+        let server = MockServer::start((0..10)
+            .map(|i| MockResponse::json(crate::mock::activity_json(1000000 + i)))
+            .collect());
+        let api = boredapi::BoredApi::with_url(server.url());
+
+        assert_eq!(aw!(api.random_many(10)).unwrap().len(), 10);
+        assert_eq!(server.hits(), 10);
+    }
+
+    #[test]
+    fn by_criteria_many() {
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let api = boredapi::BoredApi::with_url(server.url());
+        let activities = aw!(api.by_criteria_many(3, |s| s.set(boredapi::TYPE, boredapi::ActivityType::Education)))
+            .unwrap();
+
+        assert_eq!(activities.len(), 3);
+        assert!(server.requests().iter().all(|r| r.starts_with("GET /api/activity?type=education ")));
+    }
+
+    #[test]
+    fn participants_validation() {
+        let sel = boredapi::CriteriaSelection::default;
+
+        assert!(sel().set(boredapi::PARTICIPANTS, 0).is_err());
+        assert!(sel().set(boredapi::PARTICIPANTS, 1).is_ok());
+        assert!(sel().set(boredapi::PARTICIPANTS, u64::MAX).is_ok());
+    }
+
+    #[test]
+    fn accessibility_validation() {
+        for criterion in [boredapi::EXACT_ACCESSIBILITY, boredapi::MIN_ACCESSIBILITY, boredapi::MAX_ACCESSIBILITY] {
+            assert!(boredapi::CriteriaSelection::default().set(criterion, 1.0).is_ok());
+        }
+
+        for criterion in [boredapi::EXACT_ACCESSIBILITY, boredapi::MIN_ACCESSIBILITY, boredapi::MAX_ACCESSIBILITY] {
+            assert!(boredapi::CriteriaSelection::default().set(criterion, 1.0001).is_err());
+        }
+    }
+
+    #[test]
+    fn price_validation() {
+        for v in [0.0, 0.5, 1.0] {
+            for criterion in [boredapi::EXACT_PRICE, boredapi::MIN_PRICE, boredapi::MAX_PRICE] {
+                assert!(boredapi::CriteriaSelection::default().set(criterion, v).is_ok());
+            }
+        }
+
+        for v in [-0.1, 1.1] {
+            for criterion in [boredapi::EXACT_PRICE, boredapi::MIN_PRICE, boredapi::MAX_PRICE] {
+                assert!(boredapi::CriteriaSelection::default().set(criterion, v).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn selection_parameters() {
+        let sel = boredapi::CriteriaSelection::default()
+            .set(boredapi::TYPE, boredapi::ActivityType::Music)
+            .unwrap();
+
+        assert_eq!(sel.parameters().len(), 1);
+        assert_eq!(sel.parameters()["type"], "music");
+        assert_eq!(sel.get(&boredapi::TYPE), Some("music"));
+        assert_eq!(sel.get(&boredapi::PARTICIPANTS), None);
+    }
+
+    #[test]
+    fn ranges() {
+        let sel = boredapi::CriteriaSelection::default()
+            .set_price_range(0.1, 0.5)
+            .and_then(|s| s.set_accessibility_range(0.0, 1.0))
+            .unwrap();
+        assert_eq!(sel.get(&boredapi::MIN_PRICE), Some("0.1"));
+        assert_eq!(sel.get(&boredapi::MAX_PRICE), Some("0.5"));
+        assert_eq!(sel.get(&boredapi::MIN_ACCESSIBILITY), Some("0"));
+        assert_eq!(sel.get(&boredapi::MAX_ACCESSIBILITY), Some("1"));
+
+        match boredapi::CriteriaSelection::default().set_price_range(0.5, 0.1) {
+            Err(Error::InvalidRange { min, max }) => {
+                assert_eq!(min, "minprice");
+                assert_eq!(max, "maxprice");
+            }
+            r => panic!("{:?}", r),
+        }
+
+        match boredapi::CriteriaSelection::default().set_accessibility_range(0.5, 1.5) {
+            Err(Error::InvalidCriterion { name }) => assert_eq!(name, "maxaccessibility"),
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn from_reqwest_error() {
+        let e: Error = aw!(reqwest::get("http://127.0.0.1:0/")).unwrap_err().into();
+        assert!(matches!(e, Error::HttpError(_)));
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = reqwest::Client::builder().timeout(std::time::Duration::from_millis(50)).build().unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let e: Error = aw!(async { client.get(&url).send().await }).unwrap_err().into();
+        assert!(matches!(e, Error::Timeout));
+    }
+
+    #[test]
+    fn random_stream() {
+        use futures::StreamExt;
+
+        let server = MockServer::start((0..3)
+            .map(|i| MockResponse::json(crate::mock::activity_json(1000000 + i)))
+            .collect());
+        let api = boredapi::BoredApi::with_url(server.url());
+        let activities: Vec<_> = aw!(api.random_stream().take(3).collect());
+
+        assert_eq!(activities.len(), 3);
+        for (i, a) in activities.into_iter().enumerate() {
+            assert_eq!(a.unwrap().key, 1000000 + i as u64);
+        }
+        assert_eq!(server.hits(), 3);
+    }
+
+    #[test]
+    fn cache() {
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let api = boredapi::BoredApi::with_url(server.url()).with_cache(std::time::Duration::from_secs(60));
+        let education = |s: boredapi::CriteriaSelection| s.set(boredapi::TYPE, boredapi::ActivityType::Education);
+
+        assert_eq!(aw!(api.by_criteria(education)).unwrap().key, 3943506);
+        assert_eq!(aw!(api.by_criteria(education)).unwrap().key, 3943506);
+        assert_eq!(server.hits(), 1);
+
+        aw!(api.by_criteria(|s| s.set(boredapi::PARTICIPANTS, 1))).unwrap();
+        assert_eq!(server.hits(), 2);
+
+        aw!(api.random()).unwrap();
+        aw!(api.random()).unwrap();
+        assert_eq!(server.hits(), 4);
+    }
+
+    #[test]
+    fn cache_expires() {
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let api = boredapi::BoredApi::with_url(server.url()).with_cache(std::time::Duration::from_millis(0));
+
+        aw!(api.by_criteria(|s| s.set(boredapi::PARTICIPANTS, 1))).unwrap();
+        aw!(api.by_criteria(|s| s.set(boredapi::PARTICIPANTS, 1))).unwrap();
+        assert_eq!(server.hits(), 2);
+    }
+
+    #[test]
+    fn cache_evicts() {
+        use boredapi::{CriteriaSelection, ResponseCache, PARTICIPANTS};
+
+        let cache = ResponseCache { ttl: std::time::Duration::from_millis(50), entries: Default::default() };
+        let solo = CriteriaSelection::default().set(PARTICIPANTS, 1).unwrap();
+        let pair = CriteriaSelection::default().set(PARTICIPANTS, 2).unwrap();
+        let len = || cache.entries.lock().unwrap().len();
+
+        cache.insert(&solo, &sample(1000000, 0.0, 0.0));
+        assert_eq!(cache.get(&solo).unwrap().key, 1000000);
+        std::thread::sleep(std::time::Duration::from_millis(60));
+        assert!(cache.get(&solo).is_none());
+        assert_eq!(len(), 0);
+
+        cache.insert(&solo, &sample(1000000, 0.0, 0.0));
+        std::thread::sleep(std::time::Duration::from_millis(60));
+        cache.insert(&pair, &sample(2000000, 0.0, 0.0));
+        assert_eq!(len(), 1);
+        assert_eq!(cache.get(&pair).unwrap().key, 2000000);
+    }
+
+    #[test]
+    fn user_agent() {
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let mut api = boredapi::BoredApi::with_user_agent("my-app/1.0");
+        api.url = server.url();
+
+        aw!(api.random()).unwrap();
+        assert!(server.requests()[0].to_lowercase().contains("\r\nuser-agent: my-app/1.0\r\n"));
+    }
+
+    #[test]
+    fn proxy() {
+        assert!(boredapi::BoredApi::with_proxy_url("http://127.0.0.1:1").is_ok());
+        assert!(boredapi::BoredApi::with_proxy_url("not a url").is_err());
+
+        let proxy = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let mut api = boredapi::BoredApi::with_proxy(reqwest::Proxy::http(&proxy.url()).unwrap()).unwrap();
+        api.url = "http://bored.invalid/api/activity".to_string();
+
+        aw!(api.random()).unwrap();
+        assert!(proxy.requests()[0].starts_with("GET http://bored.invalid/api/activity "));
+    }
+
+    #[test]
+    fn random_raw() {
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let api = boredapi::BoredApi::with_url(server.url());
+        let (activity, json) = aw!(api.random_raw()).unwrap();
+
+        assert_eq!(json["activity"], "Learn Express.js");
+        assert_eq!(activity.description, "Learn Express.js");
+
+        let mut malformed = crate::mock::activity_json(3943506);
+        malformed["key"] = "oops".into();
+        let server = MockServer::start(vec![MockResponse::json(malformed.clone())]);
+        let api = boredapi::BoredApi::with_url(server.url());
+
+        match aw!(api.by_criteria_raw(Ok)) {
+            Err(Error::UnparsedResponse { raw, error }) => {
+                assert_eq!(raw, malformed);
+                assert!(matches!(*error, Error::Deserialization { field, .. } if field == "key"));
+            }
+            r => panic!("{:?}", r),
+        }
+
+        let server = MockServer::start(vec![MockResponse::json(serde_json::json!({
+            "error": "No activity found with the specified parameters"
+        }))]);
+        let api = boredapi::BoredApi::with_url(server.url());
+        assert!(matches!(aw!(api.random_raw()), Err(Error::NoActivityFound)));
+    }
+
+    #[test]
+    fn by_key() {
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let api = boredapi::BoredApi::with_url(server.url());
+
+        assert_eq!(aw!(api.by_key(3943506)).unwrap().key, 3943506);
+        assert!(server.requests()[0].starts_with("GET /api/activity?key=3943506 "));
+
+        match aw!(api.by_key(12345)) {
+            Err(Error::InvalidCriterion { name }) => assert_eq!(name, "key"),
+            r => panic!("{:?}", r),
+        }
+        assert_eq!(server.hits(), 1);
+    }
+
+    #[test]
+    fn activity_type_iter() {
+        use boredapi::IntoEnumIterator;
+
+        assert_eq!(boredapi::ActivityType::iter().count(), 9);
+        assert_eq!(boredapi::ActivityType::iter().map(|t| t.to_string()).collect::<Vec<_>>(),
+                   ["education", "recreational", "social", "diy", "charity", "cooking", "relaxation", "music", "busywork"]);
+    }
+
+    #[test]
+    fn activity_type_default() {
+        assert_eq!(boredapi::ActivityType::default(), boredapi::ActivityType::Recreational);
+    }
+
+    #[test]
+    fn sort_activities() {
+        let mut activities = [sample(1000001, 0.5, 0.1), sample(1000002, 0.0, 0.9), sample(1000003, 0.2, 0.4)];
+
+        activities.sort_by(Activity::cmp_by_price);
+        assert_eq!(activities.iter().map(|a| a.key).collect::<Vec<_>>(), [1000002, 1000003, 1000001]);
+
+        activities.sort_by(Activity::cmp_by_accessibility);
+        assert_eq!(activities.iter().map(|a| a.key).collect::<Vec<_>>(), [1000001, 1000003, 1000002]);
+    }
+
+    #[test]
+    fn clone_activity() {
+        let a = Activity::new("Learn Express.js".to_string(),
+                              0.25,
+                              boredapi::ActivityType::Education,
+                              1,
+                              0.1,
+                              Some(url::Url::parse("https://expressjs.com/").unwrap()),
+                              3943506);
+        let b = a.clone();
+
+        assert_eq!(b.description, a.description);
+        assert_eq!(b.accessibility, a.accessibility);
+        assert_eq!(b.activity_type, a.activity_type);
+        assert_eq!(b.participants, a.participants);
+        assert_eq!(b.price, a.price);
+        assert_eq!(b.link, a.link);
+        assert_eq!(b.key, a.key);
+    }
+
+    #[test]
+    fn activity_eq() {
+        assert_eq!(sample(3943506, 0.1, 0.25), sample(3943506, 0.1, 0.25));
+        assert_ne!(sample(3943506, 0.1, 0.25), sample(3943507, 0.1, 0.25));
+    }
+
+    #[test]
+    fn activity_hash() {
+        let mut set = std::collections::HashSet::new();
+        set.insert(sample(3943506, 0.1, 0.25));
+        set.insert(sample(3943506, 0.8, 0.5));
+
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn backends() {
+        use boredapi::{parse_activity, Backend, ParseMode};
+
+        let legacy = parse_activity(serde_json::json!({
+            "activity": "Learn how to play a new sport",
+            "accessibility": 0.2,
+            "type": "recreational",
+            "participants": 1,
+            "price": 0.1,
+            "link": "",
+            "key": "5808228"
+        }), Backend::Legacy, ParseMode::Lenient).unwrap();
+        assert_eq!(legacy.accessibility, 0.2);
+        assert_eq!(legacy.key, 5808228);
+
+        let appbrewery = serde_json::json!({
+            "activity": "Learn how to play a new sport",
+            "availability": 0.2,
+            "type": "recreational",
+            "participants": 1,
+            "price": 0.1,
+            "accessibility": "Minor challenges",
+            "duration": "hours",
+            "kidFriendly": true,
+            "link": "",
+            "key": "5808228"
+        });
+        assert_eq!(parse_activity(appbrewery.clone(), Backend::AppBrewery, ParseMode::Lenient).unwrap().accessibility, 0.2);
+        assert!(parse_activity(appbrewery.clone(), Backend::Legacy, ParseMode::Lenient).is_err());
+
+        let server = MockServer::start(vec![MockResponse::json(appbrewery)]);
+        let mut api = boredapi::BoredApi::appbrewery();
+        assert_eq!(api.url, boredapi::APPBREWERY_URL);
+        api.url = server.url();
+        assert_eq!(aw!(api.random()).unwrap().key, 5808228);
+    }
+
+    #[test]
+    fn rate_limit() {
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let api = boredapi::BoredApi::with_url(server.url()).with_rate_limit(20);
+        let start = std::time::Instant::now();
+
+        aw!(api.random_many(5)).unwrap();
+        assert!(start.elapsed() >= std::time::Duration::from_millis(200));
+        assert_eq!(server.hits(), 5);
+    }
+
+    #[test]
+    fn builder() {
+        let server = MockServer::start(vec![
+            MockResponse::status(503),
+            MockResponse::json(crate::mock::activity_json(3943506)),
+        ]);
+        let api = boredapi::BoredApi::builder()
+            .url(server.url())
+            .user_agent("my-app/1.0")
+            .timeout(std::time::Duration::from_secs(5))
+            .retry(boredapi::RetryPolicy::new(1,
+                                              std::time::Duration::from_millis(1),
+                                              std::time::Duration::from_millis(1)))
+            .build()
+            .unwrap();
+
+        assert_eq!(aw!(api.random()).unwrap().key, 3943506);
+        assert_eq!(server.hits(), 2);
+        assert!(server.requests()[1].to_lowercase().contains("\r\nuser-agent: my-app/1.0\r\n"));
+    }
+
+    #[test]
+    fn with_client() {
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let client = reqwest::Client::builder().user_agent("shared-client").build().unwrap();
+        let api = boredapi::BoredApi::with_client(server.url(), client);
+
+        assert_eq!(aw!(api.random()).unwrap().key, 3943506);
+        assert!(server.requests()[0].to_lowercase().contains("\r\nuser-agent: shared-client\r\n"));
+    }
+
+    #[test]
+    fn headers() {
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("X-Request-Id", reqwest::header::HeaderValue::from_static("42"));
+        let mut api = boredapi::BoredApi::with_headers(headers);
+        api.url = server.url();
+
+        aw!(api.random()).unwrap();
+        assert!(server.requests()[0].to_lowercase().contains("\r\nx-request-id: 42\r\n"));
+    }
+
+    #[test]
+    fn unknown_activity_type() {
+        let mut json = crate::mock::activity_json(3943506);
+        json["type"] = "gardening".into();
+        let server = MockServer::start(vec![MockResponse::json(json)]);
+        let api = boredapi::BoredApi::with_url(server.url());
+
+        assert_eq!(aw!(api.random()).unwrap().activity_type, boredapi::ActivityType::Unknown("gardening".into()));
+
+        match aw!(api.with_parse_mode(boredapi::ParseMode::Strict).random()) {
+            Err(Error::Deserialization { field, .. }) => assert_eq!(field, "type"),
+            r => panic!("{:?}", r),
+        }
+
+        assert_eq!(boredapi::ActivityType::from_str_lenient("Music"), boredapi::ActivityType::Music);
+        assert!("gardening".parse::<boredapi::ActivityType>().is_err());
+    }
+
+    #[test]
+    fn numeric_strings() {
+        use boredapi::{parse_activity, Backend, ParseMode};
+
+        let mut json = crate::mock::activity_json(3943506);
+        let number = parse_activity(json.clone(), Backend::Legacy, ParseMode::Lenient).unwrap();
+        json["price"] = "0.1".into();
+        json["accessibility"] = "0.25".into();
+        let string = parse_activity(json.clone(), Backend::Legacy, ParseMode::Lenient).unwrap();
+
+        assert_eq!(string.price, number.price);
+        assert_eq!(string.accessibility, number.accessibility);
+
+        json["price"] = "cheap".into();
+        assert!(parse_activity(json, Backend::Legacy, ParseMode::Lenient).is_err());
+    }
+
+    #[test]
+    fn parse_mode_numeric_strings() {
+        use boredapi::{parse_activity, Backend, ParseMode};
+
+        let mut json = crate::mock::activity_json(3943506);
+        json["price"] = "0.5".into();
+        assert_eq!(parse_activity(json.clone(), Backend::Legacy, ParseMode::Lenient).unwrap().price, 0.5);
+        match parse_activity(json, Backend::Legacy, ParseMode::Strict) {
+            Err(Error::Deserialization { field, reason }) => assert_eq!((field.as_str(), reason.as_str()), ("price", "expected number")),
+            r => panic!("{:?}", r),
+        }
+
+        let mut json = crate::mock::activity_json(3943506);
+        json["accessibility"] = "0.25".into();
+        match parse_activity(json, Backend::Legacy, ParseMode::Strict) {
+            Err(Error::Deserialization { field, .. }) => assert_eq!(field, "accessibility"),
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn deserialization_errors() {
+        use boredapi::{parse_activity, Backend, ParseMode};
+
+        let parse = |json| parse_activity(json, Backend::Legacy, ParseMode::Lenient);
+
+        let mut json = crate::mock::activity_json(3943506);
+        json.as_object_mut().unwrap().remove("activity");
+        match parse(json) {
+            Err(Error::Deserialization { field, reason }) => {
+                assert_eq!(field, "activity");
+                assert_eq!(reason, "missing field");
+            }
+            r => panic!("{:?}", r),
+        }
+
+        let mut json = crate::mock::activity_json(3943506);
+        json["key"] = "abc".into();
+        match parse(json) {
+            Err(Error::Deserialization { field, reason }) => {
+                assert_eq!(field, "key");
+                assert_eq!(reason, "expected numeric string");
+            }
+            r => panic!("{:?}", r),
+        }
+
+        assert!(matches!(parse(serde_json::json!([1, 2, 3])), Err(Error::BadResponse)));
+    }
+
+    #[test]
+    fn set_raw() {
+        let sel = boredapi::CriteriaSelection::default().set_raw("kidFriendly", "true");
+        assert_eq!(sel.parameters()["kidFriendly"], "true");
+    }
+
+    #[test]
+    fn connect_timeout() {
+        let blackhole = crate::mock::Blackhole::new();
+        let mut api = boredapi::BoredApi::with_connect_timeout(std::time::Duration::from_millis(100));
+        api.url = format!("http://{}/api/activity", blackhole.addr);
+        let start = std::time::Instant::now();
+
+        match aw!(api.random()) {
+            Err(Error::Timeout) => {}
+            r => panic!("{:?}", r),
+        }
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    fn single_criterion() {
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let api = boredapi::BoredApi::with_url(server.url());
+
+        aw!(api.by_type(boredapi::ActivityType::Education)).unwrap();
+        aw!(api.by_participants(1)).unwrap();
+        aw!(api.by_price(0.1)).unwrap();
+        aw!(api.by_accessibility(0.25)).unwrap();
+
+        let queries: Vec<_> = server.requests()
+            .iter()
+            .map(|r| r.split(' ').nth(1).unwrap().to_string())
+            .collect();
+        assert_eq!(queries, ["/api/activity?type=education",
+            "/api/activity?participants=1",
+            "/api/activity?price=0.1",
+            "/api/activity?accessibility=0.25"]);
+
+        match aw!(api.by_accessibility(2.0)) {
+            Err(Error::InvalidCriterion { name }) => assert_eq!(name, "accessibility"),
+            r => panic!("{:?}", r),
+        }
+        assert_eq!(server.hits(), 4);
+    }
+
+    #[test]
+    fn price_helpers() {
+        assert!(sample(1000000, 0.0, 0.5).is_free());
+        assert!(sample(1000000, 0.0, 0.5).is_cheap());
+        assert!(!sample(1000000, 0.2, 0.5).is_free());
+        assert!(sample(1000000, 0.2, 0.5).is_cheap());
+        assert!(!sample(1000000, 0.8, 0.5).is_free());
+        assert!(!sample(1000000, 0.8, 0.5).is_cheap());
+    }
+
+    #[test]
+    fn price_category() {
+        use boredapi::PriceCategory::*;
+
+        for (price, category) in [(0.0, Free), (0.1, Low), (0.33, Medium), (0.5, Medium), (0.66, High), (1.0, High)] {
+            assert_eq!(sample(1000000, price, 0.5).price_category(), category);
+        }
+        assert_eq!(Medium.to_string(), "medium");
+    }
+
+    #[test]
+    fn participants_category() {
+        use boredapi::ParticipantsCategory::*;
+
+        for (participants, category) in [(0, Unknown), (1, Solo), (2, Pair), (3, SmallGroup), (5, SmallGroup), (6, LargeGroup)] {
+            let mut activity = sample(1000000, 0.0, 0.5);
+            activity.participants = participants;
+            assert_eq!(activity.participants_category(), category);
+        }
+        assert_eq!(SmallGroup.to_string(), "small group");
+    }
+
+    #[test]
+    fn accessibility_percentage() {
+        for (accessibility, percentage, label) in [(0.0, 0.0, "very accessible"),
+                                                   (0.5, 50.0, "moderately accessible"),
+                                                   (1.0, 100.0, "hardly accessible"),
+                                                   (1.5, 100.0, "hardly accessible"),
+                                                   (-0.5, 0.0, "very accessible")] {
+            let activity = sample(1000000, 0.0, accessibility);
+            assert_eq!(activity.accessibility_percentage(), percentage);
+            assert_eq!(activity.accessibility_label(), label);
+        }
+    }
+
+    #[test]
+    fn activity_builder() {
+        let activity = Activity::builder().description("Go fishing").price(0.3).build();
+
+        assert_eq!(activity.description, "Go fishing");
+        assert_eq!(activity.price, 0.3);
+        assert_eq!(activity.accessibility, 0.0);
+        assert_eq!(activity.activity_type, boredapi::ActivityType::Recreational);
+        assert_eq!(activity.participants, 1);
+        assert_eq!(activity.link, None);
+        assert_eq!(activity.key, 1000000);
+    }
+
+    #[test]
+    fn try_new() {
+        let new = |accessibility, participants, price, key| {
+            Activity::try_new(String::from("Go fishing"), accessibility, boredapi::ActivityType::Recreational,
+                              participants, price, None, key)
+        };
+
+        assert_eq!(new(0.5, 1, 1.0, 9999999).unwrap().key, 9999999);
+
+        for (activity, criterion) in [(new(-0.1, 1, 0.0, 1000000), "accessibility"),
+                                      (new(0.0, 0, 0.0, 1000000), "participants"),
+                                      (new(0.0, 1, 5.0, 1000000), "price"),
+                                      (new(0.0, 1, 0.0, 123), "key")] {
+            match activity {
+                Err(Error::InvalidCriterion { name }) => assert_eq!(name, criterion),
+                r => panic!("{:?}", r),
+            }
+        }
+    }
+
+    #[test]
+    fn try_from_value() {
+        use std::convert::TryFrom;
+
+        assert_eq!(Activity::try_from(crate::mock::activity_json(3943506)).unwrap().key, 3943506);
+
+        match Activity::try_from(serde_json::json!({ "error": "Something went wrong" })) {
+            Err(Error::ApiError(message)) => assert_eq!(message, "Something went wrong"),
+            r => panic!("{:?}", r),
+        }
+
+        match Activity::try_from(serde_json::json!([1, 2, 3])) {
+            Err(Error::BadResponse) => {}
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn try_by_criteria() {
+        let server = MockServer::start(vec![
+            MockResponse::json(crate::mock::activity_json(3943506)),
+            MockResponse::json(serde_json::json!({ "error": "No activity found with the specified parameters" })),
+            MockResponse::json(serde_json::json!({ "error": "Failed to query due to error in arguments" })),
+        ]);
+        let api = boredapi::BoredApi::with_url(server.url());
+
+        assert_eq!(aw!(api.try_random()).unwrap().unwrap().key, 3943506);
+        assert!(aw!(api.try_by_criteria(|s| s.set(boredapi::PARTICIPANTS, 42))).unwrap().is_none());
+        match aw!(api.try_random()) {
+            Err(Error::ApiError(_)) => {}
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn random_where() {
+        let mut paid = crate::mock::activity_json(1000000);
+        paid["price"] = serde_json::json!(0.5);
+        let mut free = crate::mock::activity_json(2000000);
+        free["price"] = serde_json::json!(0.0);
+        let server = MockServer::start(vec![MockResponse::json(paid.clone()), MockResponse::json(free)]);
+        let api = boredapi::BoredApi::with_url(server.url());
+
+        assert_eq!(aw!(api.random_where(5, Activity::is_free)).unwrap().key, 2000000);
+        assert_eq!(server.hits(), 2);
+
+        let server = MockServer::start(vec![MockResponse::json(paid)]);
+        let api = boredapi::BoredApi::with_url(server.url());
+
+        match aw!(api.random_where(3, Activity::is_free)) {
+            Err(Error::ExhaustedAttempts { attempts }) => assert_eq!(attempts, 3),
+            r => panic!("{:?}", r),
+        }
+        assert_eq!(server.hits(), 3);
+    }
+
+    #[test]
+    fn query() {
+        use std::convert::TryFrom;
+        use boredapi::{ActivityQuery, CriteriaSelection};
+
+        assert!(CriteriaSelection::try_from(ActivityQuery::default()).unwrap().parameters().is_empty());
+
+        let query = ActivityQuery {
+            activity_type: Some(boredapi::ActivityType::Music),
+            participants: Some(2),
+            max_price: Some(0.5),
+            ..ActivityQuery::default()
+        };
+        let sel = CriteriaSelection::try_from(query.clone()).unwrap();
+        assert_eq!(sel.parameters().len(), 3);
+        assert_eq!(sel.get(&boredapi::TYPE), Some("music"));
+        assert_eq!(sel.get(&boredapi::PARTICIPANTS), Some("2"));
+        assert_eq!(sel.get(&boredapi::MAX_PRICE), Some("0.5"));
+
+        let invalid = ActivityQuery { key: Some(42), ..ActivityQuery::default() };
+        match CriteriaSelection::try_from(invalid) {
+            Err(Error::InvalidCriterion { name }) => assert_eq!(name, "key"),
+            r => panic!("{:?}", r),
+        }
+
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let api = boredapi::BoredApi::with_url(server.url());
+
+        assert_eq!(aw!(api.query(ActivityQuery::default())).unwrap().key, 3943506);
+        assert!(server.requests()[0].starts_with("GET /api/activity "));
+        aw!(api.query(query)).unwrap();
+        assert!(server.requests()[1].contains("participants=2"));
+    }
+
+    #[test]
+    fn criteria_selection_display() {
+        use boredapi::{ActivityType::Music, CriteriaSelection, PARTICIPANTS, TYPE};
+
+        assert_eq!(CriteriaSelection::default().to_string(), "");
+
+        let a = CriteriaSelection::default().set(TYPE, Music).unwrap().set(PARTICIPANTS, 2).unwrap();
+        let b = CriteriaSelection::default().set(PARTICIPANTS, 2).unwrap().set(TYPE, Music).unwrap();
+        assert_eq!(a.to_string(), "participants=2&type=music");
+        assert_eq!(b.to_string(), a.to_string());
+    }
+
+    #[test]
+    fn key() {
+        use boredapi::Key;
+
+        for (key, valid) in [(999_999, false), (1_000_000, true), (9_999_999, true), (10_000_000, false)] {
+            match Key::new(key) {
+                Ok(k) => {
+                    assert!(valid);
+                    assert_eq!(u64::from(k), key);
+                    assert_eq!(k.to_string(), key.to_string());
+                }
+                Err(Error::InvalidCriterion { name }) => {
+                    assert!(!valid);
+                    assert_eq!(name, "key");
+                }
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+
+        let sel = boredapi::CriteriaSelection::default().set(boredapi::KEY, Key::new(3943506).unwrap()).unwrap();
+        assert_eq!(sel.get(&boredapi::KEY), Some("3943506"));
+    }
+
+    #[test]
+    fn accessibility() {
+        use boredapi::Accessibility;
+
+        assert_eq!(Accessibility::new(-0.0).unwrap().to_string(), "0%");
+        assert_eq!(Accessibility::new(0.0).unwrap().value(), 0.0);
+        assert_eq!(Accessibility::new(1.0).unwrap().to_string(), "100%");
+        match Accessibility::new(1.1) {
+            Err(Error::InvalidCriterion { name }) => assert_eq!(name, "accessibility"),
+            r => panic!("{:?}", r),
+        }
+
+        let max = Accessibility::new(0.5).unwrap();
+        let sel = boredapi::CriteriaSelection::default().set(boredapi::MAX_ACCESSIBILITY, max).unwrap();
+        assert_eq!(sel.get(&boredapi::MAX_ACCESSIBILITY), Some("0.5"));
+
+        let sel = boredapi::CriteriaSelection::default()
+            .set_accessibility_range(Accessibility::new(0.1).unwrap(), max)
+            .unwrap();
+        assert_eq!(sel.get(&boredapi::MIN_ACCESSIBILITY), Some("0.1"));
+    }
+
+    #[test]
+    fn price() {
+        use boredapi::Price;
+
+        assert_eq!(Price::free().value(), 0.0);
+        assert_eq!(Price::new(0.0).unwrap(), Price::free());
+        assert_eq!(Price::new(1.0).unwrap().to_string(), "1");
+        for price in [-0.1, 1.1, f64::NAN] {
+            match Price::new(price) {
+                Err(Error::InvalidCriterion { name }) => assert_eq!(name, "price"),
+                r => panic!("{:?}", r),
+            }
+        }
+
+        let sel = boredapi::CriteriaSelection::default().set(boredapi::EXACT_PRICE, Price::free()).unwrap();
+        assert_eq!(sel.get(&boredapi::EXACT_PRICE), Some("0"));
+
+        let sel = boredapi::CriteriaSelection::default().set(boredapi::MAX_PRICE, Price::free()).unwrap();
+        assert_eq!(sel.get(&boredapi::MAX_PRICE), Some("0"));
+    }
+
+    #[cfg(feature = "logging")]
+    #[test]
+    fn logging() {
+        struct Recorder(std::sync::Mutex<Vec<(log::Level, String)>>);
+
+        impl log::Log for Recorder {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+
+            fn log(&self, record: &log::Record) {
+                self.0.lock().unwrap().push((record.level(), record.args().to_string()));
+            }
+
+            fn flush(&self) {}
+        }
+
+        static RECORDER: Recorder = Recorder(std::sync::Mutex::new(Vec::new()));
+        log::set_logger(&RECORDER).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        aw!(boredapi::BoredApi::with_url(server.url()).by_type(boredapi::ActivityType::Music)).unwrap();
+
+        let records = RECORDER.0.lock().unwrap();
+        assert!(records.contains(&(log::Level::Debug, format!("GET {}?type=music", server.url()))));
+        assert!(records.contains(&(log::Level::Info, "fetched activity 3943506".to_string())));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn tracing() {
+        use std::sync::{Arc, Mutex};
+        use tracing::{field, span, Event, Metadata, Subscriber};
+
+        struct Span {
+            metadata: &'static Metadata<'static>,
+            parent: Option<u64>,
+            fields: std::collections::HashMap<String, String>,
+        }
+
+        struct Fields<'a>(&'a mut std::collections::HashMap<String, String>);
+
+        impl field::Visit for Fields<'_> {
+            fn record_debug(&mut self, field: &field::Field, value: &dyn std::fmt::Debug) {
+                self.0.insert(field.name().to_string(), format!("{:?}", value));
+            }
+        }
+
+        /// Records all spans, their parents and their fields.
+        #[derive(Clone, Default)]
+        struct Collector {
+            spans: Arc<Mutex<Vec<Span>>>,
+            entered: Arc<Mutex<Vec<u64>>>,
+        }
+
+        impl Subscriber for Collector {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, attributes: &span::Attributes<'_>) -> span::Id {
+                let parent = match attributes.parent() {
+                    Some(parent) => Some(parent.into_u64()),
+                    None if attributes.is_contextual() => self.entered.lock().unwrap().last().copied(),
+                    None => None,
+                };
+                let mut span = Span { metadata: attributes.metadata(), parent, fields: Default::default() };
+                attributes.record(&mut Fields(&mut span.fields));
+
+                let mut spans = self.spans.lock().unwrap();
+                spans.push(span);
+                span::Id::from_u64(spans.len() as u64)
+            }
+
+            fn record(&self, id: &span::Id, values: &span::Record<'_>) {
+                let mut spans = self.spans.lock().unwrap();
+                values.record(&mut Fields(&mut spans[id.into_u64() as usize - 1].fields));
+            }
+
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+            fn event(&self, _: &Event<'_>) {}
+
+            fn enter(&self, id: &span::Id) {
+                self.entered.lock().unwrap().push(id.into_u64());
+            }
+
+            fn exit(&self, _: &span::Id) {
+                self.entered.lock().unwrap().pop();
+            }
+
+            fn current_span(&self) -> tracing_core::span::Current {
+                match self.entered.lock().unwrap().last() {
+                    Some(&id) => tracing_core::span::Current::new(span::Id::from_u64(id),
+                                                    self.spans.lock().unwrap()[id as usize - 1].metadata),
+                    None => tracing_core::span::Current::none(),
+                }
+            }
+        }
+
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let api = boredapi::BoredApi::with_url(server.url());
+        let collector = Collector::default();
+
+        tracing::subscriber::with_default(collector.clone(), || {
+            aw!(api.by_type(boredapi::ActivityType::Music)).unwrap();
+            aw!(api.random_many(2)).unwrap();
+        });
+
+        let spans = collector.spans.lock().unwrap();
+        let requests: Vec<_> = spans.iter().filter(|s| s.metadata.name() == "request").collect();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].fields["query"], "type=music");
+        assert_eq!(requests[0].fields["outcome"], "\"ok\"");
+        assert!(requests[0].fields.contains_key("elapsed_ms"));
+        assert_eq!(requests[0].parent, None);
+
+        let many = spans.iter().position(|s| s.metadata.name() == "by_criteria_many").unwrap() as u64 + 1;
+        assert_eq!(spans[many as usize - 1].fields["n"], "2");
+        assert!(requests[1..].iter().all(|s| s.parent == Some(many)));
+    }
+
+    #[test]
+    fn random_timed() {
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let api = boredapi::BoredApi::with_url(server.url());
+
+        let (activity, elapsed) = aw!(api.random_timed()).unwrap();
+        assert_eq!(activity.key, 3943506);
+        assert!(elapsed > std::time::Duration::from_secs(0));
+
+        let (activity, elapsed) = aw!(api.by_criteria_timed(|s| s.set(boredapi::PARTICIPANTS, 1))).unwrap();
+        assert_eq!(activity.key, 3943506);
+        assert!(elapsed > std::time::Duration::from_secs(0));
+    }
+
+    #[test]
+    fn with_fallbacks() {
+        let primary = MockServer::start(vec![MockResponse::status(503)]);
+        let broken = MockServer::start(vec![MockResponse::status(500)]);
+        let fallback = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let api = boredapi::BoredApi::with_fallbacks(primary.url(), vec![broken.url(), fallback.url()]);
+
+        assert_eq!(aw!(api.random()).unwrap().key, 3943506);
+        assert_eq!((primary.hits(), broken.hits(), fallback.hits()), (1, 1, 1));
+
+        let primary = MockServer::start(vec![MockResponse::json(serde_json::json!({ "error": "Something went wrong" }))]);
+        let api = boredapi::BoredApi::with_fallbacks(primary.url(), vec![fallback.url()]);
+
+        match aw!(api.random()) {
+            Err(Error::ApiError(_)) => {}
+            r => panic!("{:?}", r),
+        }
+        assert_eq!(fallback.hits(), 1);
+    }
+
+    /// Answers every request with the same JSON value, recording the query parameters.
+    #[derive(Debug)]
+    struct MockTransport {
+        response: serde_json::Value,
+        requests: std::sync::Mutex<Vec<std::collections::HashMap<String, String>>>,
+    }
+
+    impl boredapi::Transport for std::sync::Arc<MockTransport> {
+        fn fetch<'a>(&'a self,
+                     _: &'a str,
+                     params: &'a std::collections::HashMap<String, String>) -> futures::future::BoxFuture<'a, Result<serde_json::Value, Error>> {
+            self.requests.lock().unwrap().push(params.clone());
+            Box::pin(futures::future::ready(Ok(self.response.clone())))
+        }
+    }
+
+    #[test]
+    fn with_transport() {
+        let transport = std::sync::Arc::new(MockTransport {
+            response: crate::mock::activity_json(3943506),
+            requests: Default::default(),
+        });
+        let api = boredapi::BoredApi::default().with_transport(transport.clone());
+
+        let activity = aw!(api.by_criteria(|s| s.set(boredapi::TYPE, boredapi::ActivityType::Music))).unwrap();
+        assert_eq!(activity.key, 3943506);
+        assert_eq!(transport.requests.lock().unwrap()[0]["type"], "music");
+    }
+
+    #[test]
+    fn build_url() {
+        use boredapi::{ActivityType::Cooking, BoredApi, EXACT_PRICE, MAX_PRICE, PARTICIPANTS, TYPE};
+
+        let api = BoredApi::with_url("http://localhost/api/activity");
+        assert_eq!(api.build_url(Ok).unwrap().as_str(), "http://localhost/api/activity");
+
+        let url = api.build_url(|s| s.set(TYPE, Cooking)?.set(PARTICIPANTS, 2)).unwrap();
+        assert_eq!(url.as_str(), "http://localhost/api/activity?participants=2&type=cooking");
+
+        let url = BoredApi::with_url("http://localhost/api/activity?token=abc").build_url(|s| s.set(TYPE, Cooking)).unwrap();
+        assert_eq!(url.as_str(), "http://localhost/api/activity?token=abc&type=cooking");
+
+        match BoredApi::with_url("not a url").build_url(Ok) {
+            Err(Error::InvalidUrl(url::ParseError::RelativeUrlWithoutBase)) => {}
+            r => panic!("{:?}", r),
+        }
+
+        match api.build_url(|s| s.set(EXACT_PRICE, 0.1)?.set(MAX_PRICE, 0.5)) {
+            Err(Error::ConflictingCriteria { a, b }) => assert_eq!((a, b), ("price", "maxprice")),
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn error_conversions() {
+        let mut json = crate::mock::activity_json(3943506);
+        assert_eq!(json.to_string().parse::<Activity>().unwrap().key, 3943506);
+
+        match "{\"activity\":".parse::<Activity>() {
+            Err(e @ Error::Json(_)) => assert!(e.source().unwrap().is::<serde_json::Error>()),
+            r => panic!("{:?}", r),
+        }
+
+        json["link"] = serde_json::json!("not a url");
+        match json.to_string().parse::<Activity>() {
+            Err(e @ Error::InvalidUrl(_)) => {
+                assert_eq!(e.source().unwrap().downcast_ref(), Some(&url::ParseError::RelativeUrlWithoutBase));
+            }
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn is_retryable() {
+        let http_error = |status| {
+            let server = MockServer::start(vec![MockResponse::status(status)]);
+            let r = aw!(async { reqwest::get(&server.url()).await.unwrap().error_for_status() });
+            Error::HttpError(r.unwrap_err())
+        };
+        let connect_error = aw!(async { reqwest::get("http://127.0.0.1:0/").await.unwrap_err() });
+
+        assert!(Error::Timeout.is_retryable());
+        assert!(Error::HttpError(connect_error).is_retryable());
+        assert!(http_error(503).is_retryable());
+        assert!(!http_error(404).is_retryable());
+        assert!(!http_error(429).is_retryable());
+
+        for e in [Error::ApiError("foo".into()),
+                  Error::NoActivityFound,
+                  Error::BadResponse,
+                  Error::InvalidCriterion { name: "key" },
+                  Error::Deserialization { field: "key".into(), reason: "missing field".into() },
+                  Error::InvalidRange { min: "minprice", max: "maxprice" },
+                  Error::ExhaustedAttempts { attempts: 3 },
+                  Error::InvalidUrl(url::ParseError::EmptyHost),
+                  Error::Json(serde_json::from_str::<serde_json::Value>("").unwrap_err())] {
+            assert!(!e.is_retryable(), "{:?}", e);
+        }
+    }
+
+    #[test]
+    fn status_code() {
+        let server = MockServer::start(vec![MockResponse::status(429)]);
+
+        let e = aw!(boredapi::BoredApi::with_url(server.url()).random()).unwrap_err();
+        assert_eq!(e.status_code(), Some(reqwest::StatusCode::TOO_MANY_REQUESTS));
+        assert_eq!(Error::Timeout.status_code(), None);
+    }
+
+    #[test]
+    fn ping() {
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506)),
+                                            MockResponse::status(200).header("Content-Type", "text/html").body("<html></html>"),
+                                            MockResponse::status(404)
+                                                .header("Content-Type", "application/json")
+                                                .body(r#"{"error": "Not found"}"#),
+                                            MockResponse::status(503)]);
+        let api = boredapi::BoredApi::with_url(server.url());
+
+        aw!(api.ping()).unwrap();
+        aw!(api.ping()).unwrap();
+        assert_eq!(aw!(api.ping()).unwrap_err().status_code(), Some(reqwest::StatusCode::NOT_FOUND));
+        assert_eq!(aw!(api.ping()).unwrap_err().status_code(), Some(reqwest::StatusCode::SERVICE_UNAVAILABLE));
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn with_compression() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(crate::mock::activity_json(3943506).to_string().as_bytes()).unwrap();
+        let server = MockServer::start(vec![MockResponse::status(200)
+            .header("Content-Type", "application/json")
+            .header("Content-Encoding", "gzip")
+            .body(encoder.finish().unwrap())]);
+        let api = boredapi::BoredApi::builder().url(server.url()).compression(true).build().unwrap();
+
+        assert_eq!(aw!(api.random()).unwrap().key, 3943506);
+        assert!(server.requests()[0].to_lowercase().contains("accept-encoding: gzip, br"));
+    }
+
+    #[test]
+    fn random_with_headers() {
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))
+            .header("X-RateLimit-Remaining", "41")]);
+
+        let (activity, headers) = aw!(boredapi::BoredApi::with_url(server.url()).random_with_headers()).unwrap();
+        assert_eq!(activity.key, 3943506);
+        assert_eq!(headers["x-ratelimit-remaining"], "41");
+    }
+
+    #[test]
+    fn unexpected_body() {
+        let html = format!("<html><body>{}</body></html>", "Service unavailable. ".repeat(20));
+        let server = MockServer::start(vec![
+            MockResponse::status(200),
+            MockResponse::status(200).header("Content-Type", "text/html").body(html.clone()),
+        ]);
+        let api = boredapi::BoredApi::with_url(server.url());
+
+        match aw!(api.random()) {
+            Err(Error::UnexpectedBody { content_type: None, snippet }) => assert_eq!(snippet, ""),
+            r => panic!("{:?}", r),
+        }
+        match aw!(api.random()) {
+            Err(Error::UnexpectedBody { content_type, snippet }) => {
+                assert_eq!(content_type.as_deref(), Some("text/html"));
+                assert_eq!(snippet, html[..200]);
+            }
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn with_file_cache() {
+        let path = std::env::temp_dir().join(format!("bored_api_file_cache_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let transport = std::sync::Arc::new(MockTransport {
+            response: crate::mock::activity_json(3943506),
+            requests: Default::default(),
+        });
+        let query = |api: boredapi::BoredApi| aw!(api.by_participants(1)).unwrap().key;
+
+        let api = boredapi::BoredApi::default().with_transport(transport.clone());
+        assert_eq!(query(api.clone().with_file_cache(boredapi::FileCache::new(&path))), 3943506);
+        assert_eq!(transport.requests.lock().unwrap().len(), 1);
+
+        assert_eq!(query(api.with_file_cache(boredapi::FileCache::new(&path))), 3943506);
+        assert_eq!(transport.requests.lock().unwrap().len(), 1);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn from_env() {
+        use boredapi::{BoredApi, DEFAULT_URL, TIMEOUT_ENV_VAR, URL_ENV_VAR};
+
+        // The environment is shared by all the tests, none of the others reads these variables.
+        static ENV: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        let _guard = ENV.lock().unwrap();
+
+        std::env::remove_var(URL_ENV_VAR);
+        std::env::remove_var(TIMEOUT_ENV_VAR);
+        assert_eq!(BoredApi::from_env().unwrap().url, DEFAULT_URL);
+
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        std::env::set_var(URL_ENV_VAR, server.url());
+        std::env::set_var(TIMEOUT_ENV_VAR, "5000");
+        assert_eq!(aw!(BoredApi::from_env().unwrap().random()).unwrap().key, 3943506);
+
+        std::env::set_var(TIMEOUT_ENV_VAR, "soon");
+        match BoredApi::from_env() {
+            Err(Error::InvalidEnvVar { name, value }) => assert_eq!((name, value.as_str()), (TIMEOUT_ENV_VAR, "soon")),
+            r => panic!("{:?}", r),
+        }
+
+        std::env::remove_var(TIMEOUT_ENV_VAR);
+        std::env::set_var(URL_ENV_VAR, "not a url");
+        match BoredApi::from_env() {
+            Err(Error::InvalidEnvVar { name, .. }) => assert_eq!(name, URL_ENV_VAR),
+            r => panic!("{:?}", r),
+        }
+
+        std::env::remove_var(URL_ENV_VAR);
+    }
+
+    #[test]
+    fn criteria_selection_remove() {
+        use boredapi::{CriteriaSelection, PARTICIPANTS, TYPE};
+
+        let sel = CriteriaSelection::default()
+            .set(TYPE, boredapi::ActivityType::Music).unwrap()
+            .set(PARTICIPANTS, 2).unwrap()
+            .remove(TYPE);
+        assert_eq!(sel.get(&TYPE), None);
+        assert_eq!(sel.get(&PARTICIPANTS), Some("2"));
+
+        assert!(sel.clear().parameters().is_empty());
+    }
+
+    #[test]
+    fn criteria_selection_merge() {
+        use boredapi::{CriteriaSelection, MAX_PRICE, PARTICIPANTS, TYPE};
+
+        let base = CriteriaSelection::default().set(PARTICIPANTS, 1).unwrap().set(MAX_PRICE, 0.5).unwrap();
+        let other = CriteriaSelection::default().set(PARTICIPANTS, 2).unwrap().set(TYPE, boredapi::ActivityType::Music).unwrap();
+
+        let merged = base.merge(other);
+        assert_eq!(merged.to_string(), "maxprice=0.5&participants=2&type=music");
+    }
+
+    #[test]
+    fn conflicting_criteria() {
+        use boredapi::{CriteriaSelection, EXACT_ACCESSIBILITY, EXACT_PRICE, MAX_ACCESSIBILITY, MAX_PRICE, MIN_PRICE};
+
+        let sel = CriteriaSelection::default().set(EXACT_PRICE, 0.0).unwrap().set(MAX_ACCESSIBILITY, 0.5).unwrap();
+        sel.validate().unwrap();
+
+        match sel.clone().set(MIN_PRICE, 0.0).unwrap().validate() {
+            Err(Error::ConflictingCriteria { a, b }) => assert_eq!((a, b), ("price", "minprice")),
+            r => panic!("{:?}", r),
+        }
+        match sel.remove(EXACT_PRICE).set(MAX_PRICE, 0.5).unwrap().set(EXACT_ACCESSIBILITY, 0.5).unwrap().validate() {
+            Err(Error::ConflictingCriteria { a, b }) => assert_eq!((a, b), ("accessibility", "maxaccessibility")),
+            r => panic!("{:?}", r),
+        }
+
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let api = boredapi::BoredApi::with_url(server.url());
+
+        match aw!(api.by_criteria(|s| s.set(EXACT_PRICE, 0.0)?.set(MAX_PRICE, 0.5))) {
+            Err(Error::ConflictingCriteria { .. }) => {}
+            r => panic!("{:?}", r),
+        }
+        assert_eq!(server.hits(), 0);
+    }
+
+    #[test]
+    fn activity_type_hash() {
+        let mut types = std::collections::HashSet::new();
+        types.insert(boredapi::ActivityType::Music);
+        types.insert(boredapi::ActivityType::Music);
+        types.insert(boredapi::ActivityType::Unknown("music".into()));
+
+        assert_eq!(types.len(), 2);
+    }
+
+    #[test]
+    fn activity_type_emoji() {
+        use boredapi::IntoEnumIterator;
+
+        for t in boredapi::ActivityType::iter() {
+            assert!(!t.emoji().is_empty(), "{:?}", t);
+        }
+        assert_eq!(boredapi::ActivityType::Unknown("gaming".into()).emoji(), "❓");
+    }
+
+    #[test]
+    fn activity_type_description() {
+        let diy = boredapi::ActivityType::Diy;
+
+        assert!(!diy.description().is_empty());
+        assert_ne!(diy.description(), diy.to_string());
+    }
+
+    #[test]
+    fn weighted_random() {
+        use boredapi::{ActivityType, IntoEnumIterator};
+        use rand::SeedableRng;
+
+        let only_music: std::collections::HashMap<_, _> = ActivityType::iter()
+            .map(|t| { let weight = if t == ActivityType::Music { 2.0 } else { 0.0 }; (t, weight) })
+            .collect();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        for _ in 0..10 {
+            assert_eq!(ActivityType::weighted_random(&only_music, &mut rng), ActivityType::Music);
+        }
+
+        let draw = |seed| {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+            (0..10).map(|_| ActivityType::weighted_random(&Default::default(), &mut rng)).collect::<Vec<_>>()
+        };
+        assert_eq!(draw(7), draw(7));
+
+        let all_zero = only_music.keys().map(|t| (t.clone(), 0.0)).collect();
+        ActivityType::weighted_random(&all_zero, &mut rng);
+    }
+
+    #[test]
+    fn random_free() {
+        let mut free = crate::mock::activity_json(3943506);
+        free["price"] = serde_json::json!(0.0);
+        let server = MockServer::start(vec![MockResponse::json(free)]);
+
+        assert!(aw!(boredapi::BoredApi::with_url(server.url()).random_free()).unwrap().is_free());
+        assert!(server.requests()[0].starts_with("GET /api/activity?maxprice=0 "));
+
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        match aw!(boredapi::BoredApi::with_url(server.url()).random_free()) {
+            Err(Error::NoActivityFound) => assert_eq!(server.hits(), 2),
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn random_accessible() {
+        let mut accessible = crate::mock::activity_json(3943506);
+        accessible["accessibility"] = serde_json::json!(0.1);
+        let server = MockServer::start(vec![MockResponse::json(accessible)]);
+
+        assert_eq!(aw!(boredapi::BoredApi::with_url(server.url()).random_accessible()).unwrap().accessibility, 0.1);
+        assert!(server.requests()[0].starts_with("GET /api/activity?maxaccessibility=0.25 "));
+    }
+
+    #[test]
+    fn collect_unique() {
+        let response = |key| MockResponse::json(crate::mock::activity_json(key));
+        let server = MockServer::start(vec![response(1000000), response(2000000), response(1000000),
+                                            response(2000000), response(3000000)]);
+        let api = boredapi::BoredApi::with_url(server.url());
+
+        let mut keys: Vec<_> = aw!(api.collect_unique(3, 10)).unwrap().iter().map(|a| a.key).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, [1000000, 2000000, 3000000]);
+        assert_eq!(server.hits(), 5);
+
+        match aw!(api.collect_unique(2, 4)) {
+            Err(Error::ExhaustedAttempts { attempts }) => assert_eq!(attempts, 4),
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn random_many_with_progress() {
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let api = boredapi::BoredApi::with_url(server.url());
+        let progress = std::sync::Mutex::new(Vec::new());
+
+        let activities = aw!(api.random_many_with_progress(10, |completed, total| {
+            progress.lock().unwrap().push((completed, total));
+        })).unwrap();
+
+        assert_eq!(activities.len(), 10);
+        assert_eq!(progress.into_inner().unwrap(), (1..=10).map(|i| (i, 10)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn stream_into() {
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let api = boredapi::BoredApi::with_url(server.url());
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<Result<Activity, Error>>(2);
+
+        let received = aw!(async {
+            let consumer = async {
+                let mut received = Vec::new();
+                while let Some(r) = rx.recv().await {
+                    received.push(r.unwrap().key);
+                }
+                received
+            };
+            futures::join!(api.stream_into(tx, 5), consumer).1
+        });
+
+        assert_eq!(received, [3943506; 5]);
+        assert_eq!(server.hits(), 5);
+    }
+
+    #[test]
+    fn random_many_cancellable() {
+        let response = MockResponse::json(crate::mock::activity_json(3943506));
+        let mut responses = vec![response.clone(); 8];
+        responses.push(response.delay(std::time::Duration::from_secs(5)));
+        let server = MockServer::start(responses);
+        let api = boredapi::BoredApi::with_url(server.url());
+        let token = boredapi::CancellationToken::new();
+        let start = std::time::Instant::now();
+
+        let result = aw!(async {
+            let cancel = async {
+                tokio::time::delay_for(std::time::Duration::from_millis(500)).await;
+                token.cancel();
+            };
+            futures::join!(api.random_many_cancellable(16, &token), cancel).0
+        });
+
+        match result {
+            Err(Error::Cancelled) => {}
+            r => panic!("{:?}", r),
+        }
+        assert!(start.elapsed() < std::time::Duration::from_secs(2));
+        assert!(server.hits() < 16);
+
+        match aw!(api.collect_unique_cancellable(1, 1, &token)) {
+            Err(Error::Cancelled) => {}
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn retry_policy_delay() {
+        use std::time::Duration;
+
+        let policy = boredapi::RetryPolicy { jitter: false, ..boredapi::RetryPolicy::default() };
+        let delays: Vec<_> = (0..=5).map(|attempt| policy.delay_for(attempt)).collect();
+        assert_eq!(delays, [100, 200, 400, 800, 1600, 3200].iter().map(|&ms| Duration::from_millis(ms)).collect::<Vec<_>>());
+        assert_eq!(policy.delay_for(6), Duration::from_secs(5));
+        assert_eq!(policy.delay_for(100), Duration::from_secs(5));
+
+        let policy = boredapi::RetryPolicy::default();
+        for attempt in 0..=5 {
+            let delay = policy.delay_for(attempt);
+            let max = Duration::from_millis(100 * 2u64.pow(attempt));
+            assert!(delay >= max / 2 && delay <= max, "{:?}", delay);
+        }
+
+        assert_eq!(boredapi::RetryPolicy::none().max_retries, 0);
+    }
+
+    #[test]
+    fn with_mirrors() {
+        let mirrors: Vec<_> = (0..3)
+            .map(|i| MockServer::start(vec![MockResponse::json(crate::mock::activity_json(1000000 + i))]))
+            .collect();
+        let api = boredapi::BoredApi::with_mirrors(mirrors.iter().map(MockServer::url).collect());
+
+        let keys: Vec<_> = (0..6).map(|_| aw!(api.random()).unwrap().key).collect();
+        assert_eq!(keys, [1000000, 1000001, 1000002, 1000000, 1000001, 1000002]);
+        assert!(mirrors.iter().all(|m| m.hits() == 2));
+
+        let down = MockServer::start(vec![MockResponse::status(503)]);
+        let api = boredapi::BoredApi::with_mirrors(vec![down.url(), mirrors[0].url()]);
+        assert_eq!(aw!(api.random()).unwrap().key, 1000000);
+        assert_eq!(aw!(api.random()).unwrap().key, 1000000);
+        assert_eq!(down.hits(), 1);
+    }
+
+    #[test]
+    fn client() {
+        let server = MockServer::start(vec![MockResponse::status(204)]);
+        let api = boredapi::BoredApi::with_user_agent("bored-test");
+
+        let response = aw!(async { api.client().get(&server.url()).send().await }).unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+        assert!(server.requests()[0].to_lowercase().contains("user-agent: bored-test"));
+    }
+
+    #[test]
+    fn parse_mode_extra_fields() {
+        let mut json = crate::mock::activity_json(3943506);
+        json["imageUrl"] = serde_json::json!("https://example.com/image.png");
+        let server = MockServer::start(vec![MockResponse::json(json)]);
+        let api = |mode| boredapi::BoredApi::with_url(server.url()).with_parse_mode(mode);
+
+        assert_eq!(aw!(api(boredapi::ParseMode::Lenient).random()).unwrap().key, 3943506);
+        match aw!(api(boredapi::ParseMode::Strict).random()) {
+            Err(Error::Deserialization { field, reason }) => assert_eq!((field.as_str(), reason.as_str()), ("imageUrl", "unexpected field")),
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn parse_mode_missing_link() {
+        use boredapi::{parse_activity, Backend, ParseMode};
+
+        let mut json = crate::mock::activity_json(3943506);
+        json.as_object_mut().unwrap().remove("link");
+
+        assert_eq!(parse_activity(json.clone(), Backend::Legacy, ParseMode::Lenient).unwrap().link, None);
+        match parse_activity(json, Backend::Legacy, ParseMode::Strict) {
+            Err(Error::Deserialization { field, reason }) => assert_eq!((field.as_str(), reason.as_str()), ("link", "missing field")),
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn extra_fields() {
+        use std::convert::TryFrom;
+
+        let mut json = crate::mock::activity_json(3943506);
+        json["imageUrl"] = serde_json::json!("https://example.com/image.png");
+
+        let activity = Activity::try_from(json).unwrap();
+        assert_eq!(activity.extra.len(), 1);
+        assert_eq!(activity.extra["imageUrl"], "https://example.com/image.png");
+        assert!(Activity::try_from(crate::mock::activity_json(3943506)).unwrap().extra.is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn extra_fields_serde() {
+        let mut json = crate::mock::activity_json(3943506);
+        json["imageUrl"] = serde_json::json!("https://example.com/image.png");
+
+        let activity: Activity = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&activity).unwrap(), json);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn appbrewery_serde() {
+        use boredapi::{parse_activity, Backend, ParseMode};
+
+        let activity = parse_activity(serde_json::json!({
+            "activity": "Learn how to play a new sport",
+            "availability": 0.2,
+            "type": "recreational",
+            "participants": 1,
+            "price": 0.1,
+            "accessibility": "Minor challenges",
+            "duration": "hours",
+            "kidFriendly": true,
+            "link": "",
+            "key": "5808228"
+        }), Backend::AppBrewery, ParseMode::Lenient).unwrap();
+        assert!(!activity.extra.contains_key("accessibility"));
+
+        let json = serde_json::to_string(&activity).unwrap();
+        assert_eq!(json.matches("\"accessibility\"").count(), 1);
+
+        let loaded: Activity = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.accessibility, 0.2);
+        assert_eq!(loaded.extra["duration"], "hours");
+        assert_eq!(loaded.extra["kidFriendly"], true);
+    }
+
+    #[test]
+    fn with_language() {
+        let server = MockServer::start(vec![MockResponse::json(crate::mock::activity_json(3943506))]);
+        let mut api = boredapi::BoredApi::with_language("de-DE");
+        api.url = server.url();
+        aw!(api.random()).unwrap();
+        assert!(server.requests()[0].to_lowercase().contains("accept-language: de-de\r\n"));
+
+        let api = boredapi::BoredApi::builder()
+            .url(server.url())
+            .user_agent("bored-test")
+            .language("fr")
+            .build()
+            .unwrap();
+        aw!(api.random()).unwrap();
+        let request = server.requests()[1].to_lowercase();
+        assert!(request.contains("accept-language: fr\r\n"));
+        assert!(request.contains("user-agent: bored-test\r\n"));
+
+        match boredapi::BoredApi::builder().language("fr\n").build() {
+            Err(Error::InvalidHeader { name, .. }) => assert_eq!(name, "Accept-Language"),
+            r => panic!("{:?}", r),
         }
     }
 }
@@ -0,0 +1,156 @@
+//! Minimal HTTP server serving canned responses, so the tests don't depend on the live API.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// A canned HTTP response.
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    delay: Duration,
+}
+
+impl MockResponse {
+    pub fn json(value: serde_json::Value) -> Self {
+        MockResponse::status(200)
+            .header("Content-Type", "application/json")
+            .body(value.to_string())
+    }
+
+    pub fn status(status: u16) -> Self {
+        MockResponse { status, headers: Vec::new(), body: Vec::new(), delay: Duration::from_secs(0) }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Makes the server wait before answering. Requests are answered one at a time, so the
+    /// following ones wait as well.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+/// Serves the given responses in order, one per connection, repeating the last one once
+/// exhausted. Every received request head is recorded.
+pub struct MockServer {
+    addr: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    pub fn start(responses: Vec<MockResponse>) -> Self {
+        assert!(!responses.is_empty());
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+
+        thread::spawn(move || {
+            for (i, stream) in listener.incoming().enumerate() {
+                let mut stream = match stream {
+                    Ok(s) => s,
+                    Err(_) => continue,
+                };
+
+                recorded.lock().unwrap().push(read_head(&mut stream));
+                let response = &responses[i.min(responses.len() - 1)];
+                thread::sleep(response.delay);
+                let _ = write_response(&mut stream, response);
+            }
+        });
+
+        MockServer { addr, requests }
+    }
+
+    /// Returns the URL of the mocked activity endpoint.
+    pub fn url(&self) -> String {
+        format!("http://{}/api/activity", self.addr)
+    }
+
+    /// Returns the heads of all requests received so far.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub fn hits(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+}
+
+/// Returns a sample activity in the Bored API response shape.
+pub fn activity_json(key: u64) -> serde_json::Value {
+    serde_json::json!({
+        "activity": "Learn Express.js",
+        "accessibility": 0.25,
+        "type": "education",
+        "participants": 1,
+        "price": 0.1,
+        "link": "https://expressjs.com/",
+        "key": key.to_string()
+    })
+}
+
+fn read_head(stream: &mut TcpStream) -> String {
+    let mut head = Vec::new();
+    let mut buf = [0; 1024];
+
+    while !head.ends_with(b"\r\n\r\n") {
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => head.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    String::from_utf8_lossy(&head).into_owned()
+}
+
+fn write_response(stream: &mut TcpStream, response: &MockResponse) -> std::io::Result<()> {
+    let mut head = format!("HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n",
+                           response.status, response.body.len());
+
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(&response.body)?;
+    stream.flush()
+}
+
+/// A local address whose connection attempts hang: the accept queue of its listener is filled up,
+/// so further connection requests are dropped.
+pub struct Blackhole {
+    pub addr: SocketAddr,
+    _listener: TcpListener,
+    _connections: Vec<TcpStream>,
+}
+
+impl Blackhole {
+    pub fn new() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut connections = Vec::new();
+
+        while let Ok(s) = TcpStream::connect_timeout(&addr, Duration::from_millis(100)) {
+            connections.push(s);
+        }
+
+        Blackhole { addr, _listener: listener, _connections: connections }
+    }
+}
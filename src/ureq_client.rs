@@ -0,0 +1,106 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::boredapi::{parse_body, Activity, CriteriaSelection, Error, DEFAULT_URL};
+
+/// Synchronous client built on ureq, sharing the parser of the other clients. ureq errors are
+/// reported as [`Error::Transport`].
+#[derive(fmt::Debug, Clone)]
+pub struct BoredApiUreq {
+    pub url: String,
+    pub agent: ureq::Agent,
+}
+
+impl Default for BoredApiUreq {
+    fn default() -> Self {
+        BoredApiUreq::with_url(DEFAULT_URL)
+    }
+}
+
+impl BoredApiUreq {
+    /// Creates an API wrapper sending requests to the given endpoint with the default agent.
+    pub fn with_url(url: impl Into<String>) -> Self {
+        BoredApiUreq { url: url.into(), agent: ureq::Agent::new() }
+    }
+
+    pub fn random(&self) -> Result<Activity, Error> {
+        self.by_criteria(Ok)
+    }
+
+    pub fn by_criteria<F>(&self, selection: F) -> Result<Activity, Error>
+        where F: FnOnce(CriteriaSelection) -> Result<CriteriaSelection, Error> {
+        let sel = selection(CriteriaSelection::default())?;
+        sel.validate()?;
+
+        let mut request = self.agent.get(&self.url);
+
+        for (name, value) in sel.parameters() {
+            request = request.query(name, value);
+        }
+
+        let response = match request.call() {
+            Ok(r) => r,
+            Err(ureq::Error::Status(status, _)) if status >= 500 || status == 429 => {
+                return Err(Error::Transport { status: Some(status), message: format!("status {}", status) });
+            }
+            // Other error responses carry an `error` field.
+            Err(ureq::Error::Status(_, r)) => r,
+            Err(e) => return Err(Error::Transport { status: None, message: e.to_string() }),
+        };
+
+        let content_type = response.header("Content-Type").map(str::to_string);
+        Activity::try_from(parse_body(content_type.as_deref(), &response.into_string()?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::boredapi::{ActivityType, Error, EXACT_ACCESSIBILITY, TYPE};
+    use crate::mock::{activity_json, MockResponse, MockServer};
+    use crate::ureq_client::BoredApiUreq;
+
+    #[test]
+    fn random() {
+        let server = MockServer::start(vec![MockResponse::json(activity_json(3943506))]);
+        let api = BoredApiUreq::with_url(server.url());
+
+        for _ in 0..2 {
+            match api.random() {
+                Ok(a) => assert_eq!(a.key, 3943506),
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn by_criteria() {
+        let server = MockServer::start(vec![MockResponse::json(activity_json(3943506))]);
+
+        match BoredApiUreq::with_url(server.url()).by_criteria(|sel| sel.set(TYPE, ActivityType::Education)) {
+            Ok(a) => assert_eq!(a.activity_type, ActivityType::Education),
+            Err(e) => panic!("{:?}", e),
+        }
+        assert!(server.requests()[0].starts_with("GET /api/activity?type=education "));
+    }
+
+    #[test]
+    fn no_activity() {
+        let server = MockServer::start(vec![MockResponse::status(404)
+            .header("Content-Type", "application/json")
+            .body(r#"{"error": "No activity found with the specified parameters"}"#)]);
+
+        match BoredApiUreq::with_url(server.url()).by_criteria(|s| s.set(EXACT_ACCESSIBILITY, 1.0)) {
+            Err(Error::NoActivityFound) => {}
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn server_error() {
+        let server = MockServer::start(vec![MockResponse::status(503)]);
+
+        let e = BoredApiUreq::with_url(server.url()).random().unwrap_err();
+        assert!(e.is_retryable());
+        assert!(matches!(e, Error::Transport { status: Some(503), .. }));
+    }
+}
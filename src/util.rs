@@ -0,0 +1,213 @@
+//! Helpers for batches of activities, such as the ones returned by
+//! [`BoredApi::random_many`](crate::boredapi::BoredApi::random_many).
+
+use std::collections::HashMap;
+use std::{fs, path};
+
+use crate::boredapi::{Activity, ActivityType, Error};
+
+/// Sorts activities from the cheapest to the most expensive.
+pub fn sort_by_price(activities: &mut [Activity]) {
+    activities.sort_by(Activity::cmp_by_price);
+}
+
+/// Sorts activities from the most expensive to the cheapest.
+pub fn sort_by_price_desc(activities: &mut [Activity]) {
+    activities.sort_by(|a, b| b.cmp_by_price(a));
+}
+
+/// Sorts activities from the most accessible to the least accessible.
+pub fn sort_by_accessibility(activities: &mut [Activity]) {
+    activities.sort_by(Activity::cmp_by_accessibility);
+}
+
+/// Sorts activities from the least accessible to the most accessible.
+pub fn sort_by_accessibility_desc(activities: &mut [Activity]) {
+    activities.sort_by(|a, b| b.cmp_by_accessibility(a));
+}
+
+/// Groups activities by type, keeping their order within each group.
+pub fn group_by_type(activities: Vec<Activity>) -> HashMap<ActivityType, Vec<Activity>> {
+    let mut groups: HashMap<_, Vec<_>> = HashMap::new();
+
+    for activity in activities {
+        groups.entry(activity.activity_type.clone()).or_default().push(activity);
+    }
+
+    groups
+}
+
+/// Summary of a batch of activities computed by [`stats`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ActivityStats {
+    pub count: usize,
+    pub avg_price: f64,
+    pub avg_accessibility: f64,
+    pub avg_participants: f64,
+    pub type_histogram: HashMap<ActivityType, usize>,
+}
+
+/// Computes the averages and the number of activities of every type. All the averages are 0 for
+/// an empty batch.
+pub fn stats(activities: &[Activity]) -> ActivityStats {
+    if activities.is_empty() {
+        return ActivityStats::default();
+    }
+
+    let count = activities.len();
+    let average = |f: fn(&Activity) -> f64| activities.iter().map(f).sum::<f64>() / count as f64;
+    let mut type_histogram = HashMap::new();
+
+    for activity in activities {
+        *type_histogram.entry(activity.activity_type.clone()).or_default() += 1;
+    }
+
+    ActivityStats {
+        count,
+        avg_price: average(|a| a.price),
+        avg_accessibility: average(|a| a.accessibility),
+        avg_participants: average(|a| a.participants as f64),
+        type_histogram,
+    }
+}
+
+/// Writes activities to a file as a JSON array, in the API response shape.
+#[cfg(feature = "serde")]
+pub fn save_activities(path: impl AsRef<path::Path>, activities: &[Activity]) -> Result<(), Error> {
+    Ok(fs::write(path, serde_json::to_vec(activities)?)?)
+}
+
+/// Reads activities written by [`save_activities`].
+#[cfg(feature = "serde")]
+pub fn load_activities(path: impl AsRef<path::Path>) -> Result<Vec<Activity>, Error> {
+    Ok(serde_json::from_slice(&fs::read(path)?)?)
+}
+
+/// Renders activities as CSV, with a header row.
+pub fn to_csv(activities: &[Activity]) -> String {
+    let mut csv = String::from("description,type,participants,price,accessibility,link,key\n");
+
+    for a in activities {
+        let link = a.link.as_ref().map(url::Url::as_str).unwrap_or("");
+        csv.push_str(&format!("{},{},{},{},{},{},{}\n",
+                              csv_field(&a.description),
+                              csv_field(&a.activity_type.to_string()),
+                              a.participants,
+                              a.price,
+                              a.accessibility,
+                              csv_field(link),
+                              a.key));
+    }
+
+    csv
+}
+
+/// Quotes a field if it contains a separator, a quote or a line break, doubling the quotes.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::boredapi::{Activity, ActivityType, Error};
+    use crate::util;
+
+    fn activities() -> Vec<Activity> {
+        vec![Activity::builder().key(1000000).price(0.5).accessibility(0.1).build(),
+             Activity::builder().key(2000000).price(0.0).accessibility(0.9).build(),
+             Activity::builder().key(3000000).price(1.0).accessibility(0.5).build()]
+    }
+
+    fn keys(activities: &[Activity]) -> Vec<u64> {
+        activities.iter().map(|a| a.key).collect()
+    }
+
+    #[test]
+    fn sort_by_price() {
+        let mut activities = activities();
+
+        util::sort_by_price(&mut activities);
+        assert_eq!(keys(&activities), [2000000, 1000000, 3000000]);
+        util::sort_by_price_desc(&mut activities);
+        assert_eq!(keys(&activities), [3000000, 1000000, 2000000]);
+    }
+
+    #[test]
+    fn sort_by_accessibility() {
+        let mut activities = activities();
+
+        util::sort_by_accessibility(&mut activities);
+        assert_eq!(keys(&activities), [1000000, 3000000, 2000000]);
+        util::sort_by_accessibility_desc(&mut activities);
+        assert_eq!(keys(&activities), [2000000, 3000000, 1000000]);
+    }
+
+    #[test]
+    fn group_by_type() {
+        let mut activities = activities();
+        activities[1].activity_type = ActivityType::Music;
+
+        let groups = util::group_by_type(activities);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(keys(&groups[&ActivityType::Recreational]), [1000000, 3000000]);
+        assert_eq!(keys(&groups[&ActivityType::Music]), [2000000]);
+    }
+
+    #[test]
+    fn stats() {
+        assert_eq!(util::stats(&[]), util::ActivityStats::default());
+
+        let mut activities = activities();
+        activities[1].activity_type = ActivityType::Music;
+        activities[2].participants = 4;
+
+        let stats = util::stats(&activities);
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.avg_price, 0.5);
+        assert_eq!(stats.avg_accessibility, 0.5);
+        assert_eq!(stats.avg_participants, 2.0);
+        assert_eq!(stats.type_histogram[&ActivityType::Recreational], 2);
+        assert_eq!(stats.type_histogram[&ActivityType::Music], 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn save_activities() {
+        let path = std::env::temp_dir().join(format!("bored_api_activities_{}.json", std::process::id()));
+        let mut activities = activities();
+        activities[0].description = "Go fishing".into();
+        activities[1].link = Some(url::Url::parse("https://example.com/").unwrap());
+
+        util::save_activities(&path, &activities).unwrap();
+        let loaded = util::load_activities(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, activities);
+        assert_eq!(loaded[0].description, "Go fishing");
+        assert_eq!(loaded[1].link, activities[1].link);
+        assert_eq!(loaded[2].price, 1.0);
+
+        match util::load_activities(&path) {
+            Err(Error::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::NotFound),
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn to_csv() {
+        let mut activities = activities();
+        activities[0].description = "Bake \"cookies\", then share".into();
+        activities[1].link = Some(url::Url::parse("https://example.com/").unwrap());
+
+        let csv = util::to_csv(&activities);
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[0], "description,type,participants,price,accessibility,link,key");
+        assert_eq!(lines[1], "\"Bake \"\"cookies\"\", then share\",recreational,1,0.5,0.1,,1000000");
+        assert_eq!(lines[2], ",recreational,1,0,0.9,https://example.com/,2000000");
+        assert_eq!(lines.len(), 4);
+    }
+}